@@ -1,26 +1,233 @@
+use std::ffi::OsStr;
+use std::path::PathBuf;
+
+use crate::bytes::{argument, inline_value};
+use crate::dialect::Dialect;
+use crate::preset;
+
+
+#[derive(Clone,Copy,PartialEq)]
 enum ParseState {
     SkipProgname,
     HelpRequested,
     VersionRequested,
     ParseOptionsAndSet1,
-    NextArgIsSet1,
+    /// The named long option awaits its argument
+    ArgumentPending(&'static str),
+    BetweenEndPending,
     Set1Written,
     Set2Written,
     ExtraArgs
 }
 
 
+/// Whether a long option takes an argument, as for getopt_long.
+#[derive(Clone,Copy,PartialEq)]
+enum HasArg {
+    No,
+    Required,
+    Optional,
+}
+
+
+/// The long options, which may be abbreviated to any unambiguous prefix.
+const LONG_OPTIONS: &[(&str, HasArg)] = &[
+    ("between", HasArg::Required),
+    ("bytes", HasArg::No),
+    ("canonicalize", HasArg::Required),
+    ("check", HasArg::No),
+    ("compat", HasArg::Required),
+    ("complement", HasArg::No),
+    ("csv", HasArg::No),
+    ("delete", HasArg::No),
+    ("delimiter", HasArg::Required),
+    ("diff", HasArg::No),
+    ("explain", HasArg::No),
+    ("extended", HasArg::No),
+    ("fields", HasArg::Required),
+    ("follow", HasArg::Required),
+    ("help", HasArg::No),
+    ("json", HasArg::No),
+    ("json-keys", HasArg::No),
+    ("line-buffered", HasArg::No),
+    ("lines", HasArg::Required),
+    ("match", HasArg::Required),
+    ("null-data", HasArg::No),
+    ("preset", HasArg::Required),
+    ("preview", HasArg::No),
+    ("record-separator", HasArg::Required),
+    ("squeeze-repeats", HasArg::No),
+    ("stats", HasArg::Optional),
+    ("truncate-set1", HasArg::No),
+    ("unbuffered", HasArg::No),
+    ("version", HasArg::No),
+];
+
+
+/// The long options of GNU tr, which a prefix shared with other options
+/// stands for, so that GNU scripts keep working as options are added.
+const GNU_OPTIONS: &[&str] = &["complement", "delete", "squeeze-repeats", "truncate-set1", "help", "version"];
+
+
 #[derive(Debug,Default)]
 pub struct Config {
     pub complement: bool,
+    /// Whether the complement is ordered by collation, with -C, rather
+    /// than by code point, with -c
+    pub collate: bool,
     pub delete: bool,
     pub squeeze: bool,
     pub truncate: bool,
+    pub extended: bool,
+    pub explain: bool,
+    /// The set to print the shortest equivalent of, with --canonicalize
+    pub canonicalize: Option<String>,
+    pub stats: bool,
+    pub frequencies: bool,
+    pub check: bool,
+    pub diff: bool,
+    pub fields: String,
+    pub delimiter: String,
+    pub csv: bool,
+    pub json: bool,
+    pub json_keys: bool,
+    pub lines: String,
+    pub pattern: String,
+    pub between: Option<(String, String)>,
+    pub null_data: bool,
+    pub record_separator: String,
+    pub unbuffered: bool,
+    pub line_buffered: bool,
+    pub follow: PathBuf,
+    pub bytes: bool,
+    /// The tr to follow where implementations differ
+    pub dialect: Dialect,
     pub help_requested: bool,
     pub version_requested: bool,
+    pub preset: String,
     pub set1: String,
     pub set2: String,
     pub first_extra_arg: String,
+    /// Stages given with `-e`, applied in order; empty unless `-e` is used.
+    pub stages: Vec<Config>,
+}
+
+
+impl Config {
+    /// Create a stage with the options given so far.
+    fn stage(&self) -> Config {
+        Config {
+            complement: self.complement,
+            collate: self.collate,
+            delete: self.delete,
+            squeeze: self.squeeze,
+            truncate: self.truncate,
+            extended: self.extended,
+            dialect: self.dialect,
+            ..Default::default()
+        }
+    }
+}
+
+
+/// Find the long option `arg` names, as getopt_long does: by its full
+/// name, or else by a prefix of exactly one name, or of exactly one of
+/// GNU tr's own options.
+///
+/// Returns the option's full name, whether it takes an argument, and the
+/// argument given with it after '=', if any.
+fn long_option(arg: &str) -> Result<(&'static str, HasArg, Option<&str>), String> {
+    let (name, value) = match arg[2..].find('=') {
+        Some(i) => (&arg[2..i + 2], Some(&arg[i + 3..])),
+        None => (&arg[2..], None)
+    };
+
+    let candidates: Vec<_> = LONG_OPTIONS.iter()
+        .filter(|(long, _)| long.starts_with(name))
+        .collect();
+
+    let gnu: Vec<_> = candidates.iter()
+        .copied()
+        .filter(|(long, _)| GNU_OPTIONS.contains(long))
+        .collect();
+
+    let &(long, has_arg) = match candidates.iter().find(|(long, _)| *long == name) {
+        Some(exact) => *exact,
+        None => match (candidates.as_slice(), gnu.as_slice()) {
+            ([], _) => return Err(format!("unrecognized option '{}'", arg)),
+            ([only], _) | (_, [only]) => *only,
+            _ => {
+                let possibilities: String = candidates.iter()
+                    .map(|(long, _)| format!(" '--{}'", long))
+                    .collect();
+
+                return Err(format!("option '--{}' is ambiguous; possibilities:{}", name, possibilities));
+            }
+        }
+    };
+
+    match (has_arg, value) {
+        (HasArg::No, Some(_)) => Err(format!("option '--{}' doesn't allow an argument", long)),
+        _ => Ok((long, has_arg, value))
+    }
+}
+
+
+/// Check that POSIX tr has the option `arg`, for `--compat=posix`: it has
+/// only -c, -C, -d and -s, besides which only --compat itself, --help and
+/// --version are allowed.
+fn posix_option(arg: &str) -> Result<(), String> {
+    let extension = match arg.starts_with("--") {
+        true => match long_option(arg)? {
+            ("compat", ..) | ("help", ..) | ("version", ..) => None,
+            (name, ..) => Some(format!("--{}", name))
+        },
+        false => arg[1..].chars().find(|c| !"cCds".contains(*c)).map(|c| format!("-{}", c))
+    };
+
+    match extension {
+        Some(option) => Err(format!("option '{}' is not in POSIX tr; it cannot be used with --compat=posix",
+                                    option)),
+        None => Ok(())
+    }
+}
+
+
+/// Find the dialect given with `--compat` among `args`, before any `--`.
+fn compat<A: AsRef<OsStr>>(args: &[A]) -> Result<Dialect, String> {
+    let mut dialect = Dialect::default();
+    let mut args = args.iter().skip(1).map(|arg| arg.as_ref().to_str());
+
+    while let Some(arg) = args.next() {
+        let arg = match arg {
+            Some(arg) => arg,
+            None => continue
+        };
+
+        if arg == "--" {
+            break;
+        }
+
+        if arg.starts_with("--") {
+            if let Ok(("compat", _, value)) = long_option(arg) {
+                match value.or_else(|| args.next().flatten()) {
+                    Some(name) => dialect = Dialect::parse(name)?,
+                    None => break
+                }
+            }
+        }
+    }
+
+    Ok(dialect)
+}
+
+
+/// Test whether `arg` is an option, rather than an operand, when options
+/// are still allowed. The single character '-' is an operand.
+fn is_option(arg: &OsStr) -> bool {
+    let arg = arg.to_string_lossy();
+
+    arg.len() > 1 && arg.starts_with('-')
 }
 
 
@@ -29,63 +236,93 @@ pub struct Config {
 /// Interpret an arg (e.g. passed from the command line) and set the
 /// corresponding named flag in the config.
 ///
-/// tr expects all its options to preceed the translation sets, and this
-/// function assumes it is called in the same order as the arguments.
-///
-/// To avoid ambiguity, set1 may not start with '-' _unless_ '--' has
-/// been used on the command line to indicate end of options:
-///
-///    tr -- '-asdf' '*'
-///
-/// However, the single character '-' is valid as set1:
-///
-///    tr '-' '*'
-///
-fn parse_option<'a>(config: &mut Config, arg: &str) -> Result<ParseState, String> {
+/// Long options may be abbreviated, as with getopt_long. Returns the state
+/// the option calls for, if it changes the state: `ArgumentPending` for an
+/// option that takes an argument, which may already be given after '=',
+/// and `HelpRequested` or `VersionRequested`.
+fn parse_option(config: &mut Config, arg: &str) -> Result<Option<ParseState>, String> {
     use ParseState::*;
 
-    let mut result = Ok(ParseOptionsAndSet1);
-
-    let is_option = arg.len() >= 2 && &arg[..2] == "--";
-    let is_switch = !is_option && arg.len() > 1 && &arg[..1] == "-";
-
-    if is_option {
-        match arg {
-            "--" => result = Ok(NextArgIsSet1),
-            "--help" => {
-                config.help_requested = true;
-                result = Ok(HelpRequested);
-            },
-            "--version" => {
-                config.version_requested = true;
-                result = Ok(VersionRequested);
-            },
-            "--complement" => config.complement = true,
-            "--delete" => config.delete = true,
-            "--squeeze-repeats" => config.squeeze = true,
-            "--truncate-set1" => config.truncate = true,
-            _ => result = Err(format!("unrecognized option '{}'", arg))
-        }
-    } else if is_switch {
+    if !arg.starts_with("--") {
         for c in arg[1..].chars() {
             match c {
-                'c' | 'C' => config.complement = true,
+                'c' => config.complement = true,
+                'C' => {
+                    config.complement = true;
+                    config.collate = true;
+                },
                 'd' => config.delete = true,
                 's' => config.squeeze = true,
                 't' => config.truncate = true,
-                _ => {
-                    result = Err(format!("invalid option -- '{}'", c));
-                    break;
-                }
+                'E' => config.extended = true,
+                'z' => config.null_data = true,
+                'u' => config.unbuffered = true,
+                _ => return Err(format!("invalid option -- '{}'", c))
             }
         }
-    } else {
-        // options exhausted; current arg is set1
-        config.set1 = arg.to_owned();
-        result = Ok(Set1Written);
+
+        return Ok(None);
+    }
+
+    let (name, has_arg, value) = long_option(arg)?;
+
+    match name {
+        "help" => return Ok(Some(HelpRequested)),
+        "version" => return Ok(Some(VersionRequested)),
+        "complement" => config.complement = true,
+        "delete" => config.delete = true,
+        "squeeze-repeats" => config.squeeze = true,
+        "truncate-set1" => config.truncate = true,
+        "extended" => config.extended = true,
+        "explain" => config.explain = true,
+        "check" => config.check = true,
+        "diff" | "preview" => config.diff = true,
+        "csv" => config.csv = true,
+        "json" => config.json = true,
+        "json-keys" => config.json_keys = true,
+        "null-data" => config.null_data = true,
+        "unbuffered" => config.unbuffered = true,
+        "line-buffered" => config.line_buffered = true,
+        "bytes" => config.bytes = true,
+        "stats" => {
+            config.stats = true;
+
+            match value {
+                None => (),
+                Some("freq") => config.frequencies = true,
+                Some(value) => return Err(format!("invalid argument ‘{}’ for ‘--stats’", value))
+            }
+        },
+        _ if has_arg == HasArg::Required => return Ok(Some(ArgumentPending(name))),
+        _ => unreachable!("unhandled option --{}", name)
+    }
+
+    Ok(None)
+}
+
+
+/// Set the argument `value` of the long option `name` in `config`,
+/// reading it as a set operand if `bytes` is set and the option's
+/// argument is one.
+fn set_argument(config: &mut Config, name: &str, value: &OsStr, bytes: bool) -> Result<(), String> {
+    let text = || argument(value, false);
+    let operand = || argument(value, bytes);
+
+    match name {
+        "preset" => config.preset = text()?,
+        "fields" => config.fields = text()?,
+        "delimiter" => config.delimiter = operand()?,
+        "lines" => config.lines = text()?,
+        "match" => config.pattern = text()?,
+        "between" => config.between = Some((text()?, String::new())),
+        "record-separator" => config.record_separator = operand()?,
+        "compat" => config.dialect = Dialect::parse(&text()?)?,
+        "canonicalize" => config.canonicalize = Some(operand()?),
+        "follow" => config.follow = value.into(),
+        _ => unreachable!("--{} takes no argument", name)
     }
 
-    result
+    Ok(())
 }
 
 
@@ -95,53 +332,298 @@ fn parse_option<'a>(config: &mut Config, arg: &str) -> Result<ParseState, String
 /// arguments if each argument is understood by tr and the combination of
 /// arguments is coherent.
 ///
+/// `--preset NAME` (or `--preset=NAME`) takes the place of SET1 and SET2,
+/// selecting one of the built-in pairs in `crate::preset`.
+///
+/// `--canonicalize SET` takes the place of SET1 and SET2, asking for the
+/// shortest set equivalent to SET; see `crate::canonical::canonicalize`.
+///
+/// `-E` (`--extended`) selects the extended set syntax, which adds set
+/// operators; see `crate::parser::parse_extended`.
+///
+/// Each `-e` begins a stage, taking options and sets of its own, to be
+/// applied in order:
+///
+///    tr -e '[:upper:]' '[:lower:]' -e -d '[:punct:]' -e -s ' '
+///
+/// Options given before the first `-e` apply to every stage.
+///
+/// `--json` and `--json-keys` confine the op to the string values and the
+/// object keys, respectively, of a JSON document; see `crate::json::json`.
+///
+/// `--lines LIST`, `--match REGEX` and `--between START END` confine the op
+/// to some lines of the input; see `crate::select::select`.
+///
+/// `-z` (`--null-data`) and `--record-separator SEP` divide the input into
+/// records, protecting the separator; see `crate::record::records`.
+///
+/// Output is block buffered unless `--line-buffered` asks for a flush after
+/// each line, or `-u` (`--unbuffered`) after each write.
+///
+/// `--follow FILE` reads FILE in place of the standard input, like
+/// `tail -f`; see `crate::follow::Follow`.
+///
+/// Arguments need not be UTF-8. With `--bytes`, the sets and the input are
+/// read byte by byte, each byte standing for the character U+0000 to U+00FF
+/// of the same value; see `crate::bytes`. Otherwise, sets must be UTF-8.
+///
+/// As with GNU getopt_long, long options may be abbreviated to any
+/// unambiguous prefix, or to any prefix of one of GNU tr's options, which
+/// wins over the rest (`--d` is `--delete`), and options may follow the
+/// sets, unless the environment variable POSIXLY_CORRECT is set, in which
+/// case the first operand ends the options. `--` always ends them:
+///
+///    tr -d -- -x
+///
+/// `--compat=gnu|bsd|posix` selects the tr to follow where implementations
+/// differ; see `crate::dialect::Dialect`. Under `bsd` and `posix`, the first
+/// operand ends the options, and `posix` allows only the options POSIX tr
+/// has, besides `--compat`, `--help` and `--version`.
+///
 /// Returns Err("message") on encountering an unrecognized option or if
 /// the combined arguments do not make sense.
-pub fn parse_args<'a, I>(args: I) -> Result<Config, String>
+pub fn parse_args<I>(args: I) -> Result<Config, String>
 where
     I: IntoIterator,
-    I::Item: AsRef<str>
+    I::Item: AsRef<OsStr>
 {
     use ParseState::*;
 
     let mut state = SkipProgname;
     let mut config: Config = Default::default();
 
-    for arg in args {
+    // the state to return to once an option has its argument
+    let mut resume = ParseOptionsAndSet1;
+    // whether '--', or an operand under POSIXLY_CORRECT, has ended options
+    let mut options_ended = false;
+    let posixly_correct = std::env::var_os("POSIXLY_CORRECT").is_some();
+
+    let args: Vec<_> = args.into_iter().collect();
+
+    // whether sets are given as bytes decides how every operand is read
+    let bytes = args.iter()
+        .skip(1)
+        .filter_map(|arg| arg.as_ref().to_str())
+        .take_while(|&arg| arg != "--")
+        .any(|arg| arg.starts_with("--") && matches!(long_option(arg), Ok(("bytes", ..))));
+
+    // the dialect decides how options and sets are read
+    let dialect = compat(&args)?;
+    let posix = dialect == Dialect::Posix;
+
+    let text = |arg: &OsStr| argument(arg, false);
+    let operand = |arg: &OsStr| argument(arg, bytes);
+
+    for arg in &args {
         let arg = arg.as_ref();
 
+        // -e ends the current stage, if any, and begins the next
+        let in_stage = !config.stages.is_empty();
+
+        if arg == "-e" && !posix && ((state == ParseOptionsAndSet1 && !options_ended)
+                           || (in_stage && matches!(state, Set1Written | Set2Written))) {
+            if let Some(stage) = config.stages.last() {
+                validate(stage, state)?;
+            }
+
+            config.stages.push(config.stage());
+            state = ParseOptionsAndSet1;
+            options_ended = false;
+            continue;
+        }
+
+        let current = match config.stages.is_empty() {
+            true => &mut config,
+            false => config.stages.last_mut().unwrap()
+        };
+
         match state {
             SkipProgname => {
                 state = ParseOptionsAndSet1;
-                continue;
-            },
-            ParseOptionsAndSet1 => {
-                match parse_option(&mut config, &arg) {
-                    Ok(newstate) => state = newstate,
-                    Err(e) => { return Err(e); }
-                }
             },
             HelpRequested | VersionRequested => {
                 break;
             },
-            NextArgIsSet1 => {
-                config.set1 = arg.to_owned();
-                state = Set1Written;
+            ArgumentPending(name) => {
+                set_argument(current, name, arg, bytes)?;
+
+                state = match name {
+                    "between" => BetweenEndPending,
+                    _ => resume
+                };
             },
-            Set1Written => {
-                config.set2 = arg.to_owned();
-                state = Set2Written;
+            BetweenEndPending => {
+                if let Some((_, end)) = &mut current.between {
+                    *end = text(arg)?;
+                }
+                state = resume;
             },
-            Set2Written => {
-                config.first_extra_arg = arg.to_owned();
-                state = ExtraArgs;
-                break;
+            _ if !options_ended && arg == "--" => {
+                options_ended = true;
+            },
+            _ if !options_ended && is_option(arg) => {
+                if posix {
+                    posix_option(&arg.to_string_lossy())?;
+                }
+
+                // options may follow operands, as getopt_long permutes them
+                match parse_option(current, &arg.to_string_lossy())? {
+                    Some(ArgumentPending(name)) => {
+                        resume = state;
+                        state = ArgumentPending(name);
+
+                        if let Some(value) = inline_value(arg) {
+                            set_argument(current, name, value, bytes)?;
+
+                            state = match name {
+                                "between" => BetweenEndPending,
+                                _ => resume
+                            };
+                        }
+                    },
+                    Some(next) => state = next,
+                    None => ()
+                }
             },
-            ExtraArgs => unreachable!()
+            _ => {
+                options_ended |= posixly_correct || !dialect.permutes();
+
+                match state {
+                    ParseOptionsAndSet1 => {
+                        current.set1 = operand(arg)?;
+                        state = Set1Written;
+                    },
+                    Set1Written => {
+                        current.set2 = operand(arg)?;
+                        state = Set2Written;
+                    },
+                    Set2Written => {
+                        current.first_extra_arg = arg.to_string_lossy().into_owned();
+                        state = ExtraArgs;
+                    },
+                    _ => ()
+                }
+            }
+        }
+    }
+
+    // options concerning the whole run may also be given within a stage
+    let stages = std::mem::take(&mut config.stages);
+
+    for stage in &stages {
+        config.explain |= stage.explain;
+        config.diff |= stage.diff;
+        config.stats |= stage.stats;
+        config.frequencies |= stage.frequencies;
+        config.csv |= stage.csv;
+        config.json |= stage.json;
+        config.json_keys |= stage.json_keys;
+        config.null_data |= stage.null_data;
+        config.unbuffered |= stage.unbuffered;
+        config.line_buffered |= stage.line_buffered;
+
+        if !stage.fields.is_empty() {
+            config.fields = stage.fields.clone();
+        }
+
+        if !stage.delimiter.is_empty() {
+            config.delimiter = stage.delimiter.clone();
         }
+
+        if !stage.lines.is_empty() {
+            config.lines = stage.lines.clone();
+        }
+
+        if !stage.pattern.is_empty() {
+            config.pattern = stage.pattern.clone();
+        }
+
+        if stage.between.is_some() {
+            config.between = stage.between.clone();
+        }
+
+        if stage.canonicalize.is_some() {
+            config.canonicalize = stage.canonicalize.clone();
+        }
+
+        if !stage.record_separator.is_empty() {
+            config.record_separator = stage.record_separator.clone();
+        }
+
+        if !stage.follow.as_os_str().is_empty() {
+            config.follow = stage.follow.clone();
+        }
+    }
+
+    config.stages = stages;
+    config.bytes = bytes;
+    config.dialect = dialect;
+
+    for stage in &mut config.stages {
+        stage.dialect = dialect;
+    }
+
+    if (config.json || config.json_keys) && !config.fields.is_empty() {
+        return Err("--fields cannot be combined with --json".to_owned());
+    }
+
+    // the report gives lines and columns of the whole input
+    if config.check {
+        let restriction = [
+            ("--fields", !config.fields.is_empty()),
+            ("--json", config.json || config.json_keys),
+            ("--null-data", config.null_data),
+            ("--record-separator", !config.record_separator.is_empty()),
+        ].iter().find(|(_, given)| *given).map(|(option, _)| *option);
+
+        if let Some(option) = restriction {
+            return Err(format!("{} cannot be combined with --check", option));
+        }
+    }
+
+    match state {
+        HelpRequested => config.help_requested = true,
+        VersionRequested => config.version_requested = true,
+        _ => validate(config.stages.last().unwrap_or(&config), state)?
+    }
+
+    Ok(config)
+}
+
+
+/// Check the coherence of `config`, given the state parsing its arguments
+/// ended in.
+fn validate(config: &Config, state: ParseState) -> Result<(), String> {
+    use ParseState::*;
+
+    match state {
+        ArgumentPending("between") | BetweenEndPending => {
+            return Err("option '--between' requires two arguments".to_owned());
+        },
+        ArgumentPending(name) => return Err(format!("option '--{}' requires an argument", name)),
+        _ => ()
+    }
+
+    if config.canonicalize.is_some() {
+        return match state {
+            Set1Written | Set2Written | ExtraArgs => Err(format!("extra operand ‘{}’", config.set1)),
+            _ => Ok(())
+        };
+    }
+
+    if !config.preset.is_empty() {
+        // the preset supplies both sets, so any operand is one too many
+        return match state {
+            Set1Written | Set2Written | ExtraArgs => {
+                Err(format!("extra operand ‘{}’", config.set1))
+            },
+            _ => match preset::lookup(&config.preset) {
+                Some(_) => Ok(()),
+                None => Err(format!("unknown preset ‘{}’", config.preset))
+            }
+        };
     }
 
-    // validate coherence of final configuration
     match state {
         ExtraArgs => {
             Err(format!("extra operand ‘{}’", config.first_extra_arg))
@@ -149,13 +631,16 @@ where
         ParseOptionsAndSet1 => {
             Err("missing operand".to_owned())
         },
+        Set2Written if config.check => {
+            Err(format!("extra operand ‘{}’", config.set2))
+        },
         Set1Written => {
             // squeeze OR delete Ok, squeeze AND delete requires set2
-            match config.squeeze ^ config.delete {
-                true => Ok(config),
+            match config.squeeze ^ config.delete || config.check {
+                true => Ok(()),
                 false => Err(format!("missing operand after ‘{}’", config.set1))
             }
         },
-        _ => Ok(config)
+        _ => Ok(())
     }
 }
@@ -0,0 +1,96 @@
+use std::borrow::Cow;
+use std::convert::TryFrom;
+use std::ffi::OsStr;
+
+
+/// Decode `bytes` for `--bytes` mode, one character per byte: byte N
+/// becomes U+00NN, as in Latin-1.
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(tr::bytes::decode(b"caf\xe9"), "café");
+/// assert_eq!(tr::bytes::decode("é".as_bytes()), "\u{c3}\u{a9}");
+/// ```
+pub fn decode(bytes: &[u8]) -> String {
+    bytes.iter().map(|&b| char::from(b)).collect()
+}
+
+
+/// Encode `s` as decoded by `decode`: each character up to U+00FF as the
+/// byte it came from, and any other, as only an op can produce, in UTF-8.
+pub fn encode(s: &str) -> Cow<'_, [u8]> {
+    if s.is_ascii() {
+        return Cow::Borrowed(s.as_bytes());
+    }
+
+    let mut bytes = Vec::with_capacity(s.len());
+
+    for c in s.chars() {
+        match u8::try_from(u32::from(c)) {
+            Ok(b) => bytes.push(b),
+            Err(_) => bytes.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes())
+        }
+    }
+
+    Cow::Owned(bytes)
+}
+
+
+/// Read a command-line argument as text: as UTF-8, or, in `--bytes` mode,
+/// byte by byte as by `decode`.
+pub fn argument(arg: &OsStr, bytes: bool) -> Result<String, String> {
+    match (bytes, arg.to_str()) {
+        (true, _) => Ok(decode(&raw(arg))),
+        (false, Some(s)) => Ok(s.to_owned()),
+        (false, None) => Err(format!("invalid UTF-8 in argument ‘{}’; use --bytes to give bytes",
+                                     escape(&raw(arg))))
+    }
+}
+
+
+/// The value given in a `--name=value` argument, if any.
+#[cfg(unix)]
+pub fn inline_value(arg: &OsStr) -> Option<&OsStr> {
+    use std::os::unix::ffi::OsStrExt;
+
+    let bytes = arg.as_bytes();
+    let i = bytes.iter().position(|&b| b == b'=')?;
+
+    Some(OsStr::from_bytes(&bytes[i + 1..]))
+}
+
+
+#[cfg(not(unix))]
+pub fn inline_value(arg: &OsStr) -> Option<&OsStr> {
+    arg.to_str()?.split_once('=').map(|(_, value)| OsStr::new(value))
+}
+
+
+/// The bytes of `arg`, where the platform has them.
+#[cfg(unix)]
+fn raw(arg: &OsStr) -> Cow<'_, [u8]> {
+    use std::os::unix::ffi::OsStrExt;
+
+    Cow::Borrowed(arg.as_bytes())
+}
+
+
+#[cfg(not(unix))]
+fn raw(arg: &OsStr) -> Cow<'_, [u8]> {
+    match arg.to_string_lossy() {
+        Cow::Borrowed(s) => Cow::Borrowed(s.as_bytes()),
+        Cow::Owned(s) => Cow::Owned(s.into_bytes())
+    }
+}
+
+
+/// Show `bytes` with any that are not printable ASCII as `\xNN`.
+fn escape(bytes: &[u8]) -> String {
+    bytes.iter()
+        .map(|&b| match b {
+            b' '..=b'~' => char::from(b).to_string(),
+            b => format!("\\x{:02x}", b)
+        })
+        .collect()
+}
@@ -0,0 +1,166 @@
+use unicode_properties::{GeneralCategoryGroup, UnicodeGeneralCategory};
+
+use crate::dialect::Dialect;
+use crate::parser::{expand_class, Syntax};
+
+
+/// The POSIX classes, largest first, so that the first whose characters
+/// are found is the largest.
+const CLASSES: &[&str] = &[
+    "[:print:]", "[:graph:]", "[:alnum:]", "[:alpha:]", "[:cntrl:]", "[:punct:]",
+    "[:lower:]", "[:upper:]", "[:xdigit:]", "[:digit:]", "[:space:]", "[:blank:]",
+];
+
+
+/// Rewrite `set` as the shortest expression in `syntax` that expands to
+/// the same graphemes, in the same order.
+///
+/// Consecutive code points become a range where that is shorter, the
+/// characters of a POSIX class in its order become the class where that
+/// covers more than a range would, and characters that are not printable
+/// or that the syntax gives a meaning, such as `-` between two others,
+/// are escaped. A
+/// `[c*]` repeat filling SET2 is kept as it is; a repeat with a count
+/// becomes the graphemes it stands for.
+///
+/// # Examples
+///
+/// ```
+/// use tr::canonical::canonicalize;
+/// use tr::parser::Syntax;
+///
+/// let syntax = Syntax::default();
+///
+/// assert_eq!(canonicalize("abcdefghijklmnopqrstuvwxyz0123456789", syntax).unwrap(), "a-z0-9");
+/// assert_eq!(canonicalize("A-Za-z", syntax).unwrap(), "[:alpha:]");
+/// assert_eq!(canonicalize("\t\n\u{b}\u{c}\r -", syntax).unwrap(), "[:space:]-");
+/// assert_eq!(canonicalize("a\\-c", syntax).unwrap(), "a\\-c");
+/// assert_eq!(canonicalize("[x*3]yz[.*]", syntax).unwrap(), "xxxyz[.*]");
+/// ```
+pub fn canonicalize(set: &str, syntax: Syntax) -> Result<String, String> {
+    let (before, fill, after) = syntax.split_set(set, "SET")?;
+    let mut output = expression(&before, syntax);
+
+    if let Some(fill) = fill {
+        output.push_str(&format!("[{}*]{}", fill, expression(&after, syntax)));
+    }
+
+    // a '-' at either end cannot form a range, though in extended syntax,
+    // it may still form an operator with the next
+    if !syntax.extended {
+        if output.starts_with("\\-") {
+            output.remove(0);
+        }
+
+        if output.ends_with("\\-") {
+            output.remove(output.len() - 2);
+        }
+
+        output = unescape_brackets(&output);
+    }
+
+    Ok(output)
+}
+
+
+/// The shortest expression for the characters of `expanded`, taking the
+/// largest class or range found at each point.
+fn expression(expanded: &str, syntax: Syntax) -> String {
+    let chars: Vec<char> = expanded.chars().collect();
+    let classes: Vec<(&str, Vec<char>)> = CLASSES.iter()
+        .map(|&class| (class, expand_class(class).chars().collect()))
+        .collect();
+
+    let mut output = String::with_capacity(expanded.len());
+    let mut i = 0;
+
+    while i < chars.len() {
+        let rest = &chars[i..];
+        let run = 1 + rest.windows(2).take_while(|w| u32::from(w[0]) + 1 == u32::from(w[1])).count();
+
+        let (text, length) = match classes.iter().find(|(_, members)| rest.starts_with(members)) {
+            Some((class, members)) if members.len() > run => (class.to_string(), members.len()),
+            _ => {
+                let literal: String = rest[..run].iter().map(|&c| escape(c, syntax)).collect();
+                let range = format!("{}-{}", escape(rest[0], syntax), escape(rest[run - 1], syntax));
+
+                match range.len() < literal.len() {
+                    true => (range, run),
+                    false => (escape(rest[0], syntax), 1)
+                }
+            }
+        };
+
+        output.push_str(&text);
+        i += length;
+    }
+
+    output
+}
+
+
+/// Drop the escape from each `\[` in `output` that could not start a
+/// class, equivalence class or repeat, `[:`, `[=` or `[c*`, as written.
+///
+/// The last are taken first, so that each is judged by what follows it
+/// once written.
+fn unescape_brackets(output: &str) -> String {
+    let mut escapes = vec![];
+    let mut chars = output.char_indices();
+
+    while let Some((i, c)) = chars.next() {
+        if c == '\\' && chars.next().is_some_and(|(_, c)| c == '[') {
+            escapes.push(i);
+        }
+    }
+
+    let mut output = output.to_owned();
+
+    for i in escapes.into_iter().rev() {
+        let rest = &output[i + 1..];
+
+        if !(rest.starts_with("[:") || rest.starts_with("[=") || rest.get(2..3) == Some("*")) {
+            output.remove(i);
+        }
+    }
+
+    output
+}
+
+
+/// Write `c` so that `syntax` reads it back as a literal.
+fn escape(c: char, syntax: Syntax) -> String {
+    match c {
+        '\\' => r"\\".to_owned(),
+        '\u{7}' => r"\a".to_owned(),
+        '\u{8}' => r"\b".to_owned(),
+        '\u{c}' => r"\f".to_owned(),
+        '\n' => r"\n".to_owned(),
+        '\r' => r"\r".to_owned(),
+        '\t' => r"\t".to_owned(),
+        '\u{b}' => r"\v".to_owned(),
+        // '-' may form a range, unless at either end, and '[' a class or
+        // repeat, unless followed by neither, as `canonicalize` allows
+        // for, and in extended syntax, these and the rest may form a
+        // nested set or operator
+        '-' | '[' => format!("\\{}", c),
+        ']' | '&' if syntax.extended => format!("\\{}", c),
+        _ if is_printable(c) => c.to_string(),
+        _ if c <= '\u{ff}' => format!("\\{:03o}", u32::from(c)),
+        // POSIX has no \u escape
+        _ if syntax.dialect == Dialect::Posix => c.to_string(),
+        _ => format!("\\u{{{:x}}}", u32::from(c))
+    }
+}
+
+
+/// Test whether `c` may be written as it is, being visible and not
+/// combining with the character before it.
+fn is_printable(c: char) -> bool {
+    match c.general_category_group() {
+        _ if c.is_control() => false,
+        GeneralCategoryGroup::Other | GeneralCategoryGroup::Mark => false,
+        GeneralCategoryGroup::Separator => c == ' ',
+        _ => true
+    }
+}
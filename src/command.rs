@@ -1,16 +1,148 @@
-use std::collections::HashSet;
-use std::io::{BufRead,Write};
+use std::borrow::Cow;
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::ffi::OsStr;
+use std::io::{BufRead,BufReader,ErrorKind,Write};
+use std::rc::Rc;
 
 use crate::arg_parser::{Config, parse_args};
-use crate::parser::{parse,map_charsets};
+use crate::bytes;
+use crate::canonical::canonicalize;
+use crate::complement::{self, Order};
+use crate::dialect::Dialect;
+use crate::diff::{diff, Style};
+use crate::explain::explain;
+use crate::fields::fields;
+use crate::follow::Follow;
+use crate::json::json;
+use crate::parser::{CharSet, Syntax};
+use crate::preset::{self, Preset};
+use crate::record::records;
+use crate::select::select;
+use crate::stats::{self, Counter, Effect, Stats};
 
 use bstr::ByteSlice;
 
 
+/// An operation applied to each input grapheme in turn, returning the
+/// text to write in its place, or None to write nothing.
+///
+/// The empty string marks a boundary in the input, such as the end of a
+/// field: an op should write nothing for it and forget any state carried
+/// from one grapheme to the next, such as the last grapheme squeezed.
+pub type Op = Box<dyn FnMut(&str) -> Option<String>>;
+
+
+/// When to flush the output while processing.
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub enum Buffering {
+    /// Only when the writer's buffer fills, and at the end of input
+    Block,
+    /// After each output line, as well
+    Line,
+    /// After each write
+    Unbuffered,
+}
+
+
+impl Buffering {
+    /// The buffering `config` calls for.
+    pub fn of(config: &Config) -> Buffering {
+        // following a file is interactive use
+        let line_buffered = config.line_buffered || !config.follow.as_os_str().is_empty();
+
+        match (config.unbuffered, line_buffered) {
+            (true, _) => Buffering::Unbuffered,
+            (false, true) => Buffering::Line,
+            (false, false) => Buffering::Block
+        }
+    }
+
+    /// Write `c` to `writer`, flushing as called for.
+    fn write<W: Write>(self, writer: &mut W, c: &[u8]) -> Result<(), std::io::Error> {
+        writer.write_all(c)?;
+
+        match self {
+            Buffering::Unbuffered => writer.flush(),
+            Buffering::Line if c.contains(&b'\n') => writer.flush(),
+            _ => Ok(())
+        }
+    }
+}
+
+
+/// What the op is applied to in turn.
+#[derive(Debug,Clone,Copy,PartialEq)]
+enum Unit {
+    Grapheme,
+    /// As in POSIX, which knows nothing of graphemes
+    Char,
+    /// As in `crate::bytes`
+    Byte,
+}
+
+
+impl Unit {
+    /// The unit `config` calls for.
+    fn of(config: &Config) -> Unit {
+        match (config.bytes, config.dialect) {
+            (true, _) => Unit::Byte,
+            (false, Dialect::Posix) => Unit::Char,
+            (false, _) => Unit::Grapheme
+        }
+    }
+}
+
+
+/// The length of `data` that can be processed before more is read, one
+/// `unit` at a time: all but a character cut short at the end, and, when
+/// reading graphemes, the grapheme before it, which more data might
+/// extend, unless that grapheme ends a line.
+fn complete(data: &[u8], unit: Unit) -> usize {
+    let mut end = data.len();
+
+    if unit == Unit::Byte {
+        return end;
+    }
+
+    // a character cut short ends with continuation bytes after its lead byte
+    for n in 1..=data.len().min(3) {
+        let byte = data[data.len() - n];
+
+        if byte & 0xc0 != 0x80 {
+            let width = match byte {
+                0xc0..=0xdf => 2,
+                0xe0..=0xef => 3,
+                0xf0..=0xf7 => 4,
+                _ => 1
+            };
+
+            if width > n {
+                end -= n;
+            }
+
+            break;
+        }
+    }
+
+    if unit == Unit::Char {
+        return end;
+    }
+
+    match data[..end].grapheme_indices().next_back() {
+        Some((_, _, g)) if g.ends_with('\n') => end,
+        Some((start, _, _)) => start,
+        None => 0
+    }
+}
+
+
 struct Tr<R, W, O> {
     reader: R,
     writer: W,
-    op: O
+    op: O,
+    buffering: Buffering,
+    unit: Unit,
 }
 
 
@@ -21,41 +153,302 @@ where
     O: FnMut(&str) -> Option<String>
 {
     pub fn process(&mut self) -> Result<(), std::io::Error> {
-        let mut buffer = self.reader.fill_buf()?;
-        let mut length = buffer.len();
-
-       while !buffer.is_empty() {
-            // FIXME: test & handle non-utf-8 input
-            // FIXME: handle case where buffer splits a grapheme
-            for b in buffer.graphemes() {
-                match (self.op)(b) {
-                    Some(c) => { self.writer.write(c.as_bytes())?; }
-                    None => continue
-                }
+        // the end of each read is held over in case the next continues it
+        let mut data: Vec<u8> = Vec::new();
+
+        loop {
+            let buffer = match self.reader.fill_buf() {
+                Ok(buffer) => buffer,
+                // nothing more yet, so a grapheme held over is taken as it is
+                Err(e) if e.kind() == ErrorKind::WouldBlock => {
+                    let unit = match self.unit {
+                        Unit::Grapheme => Unit::Char,
+                        unit => unit
+                    };
+
+                    let end = complete(&data, unit);
+                    self.apply(&data[..end])?;
+                    data.drain(..end);
+                    self.writer.flush()?;
+                    continue;
+                },
+                Err(e) => return Err(e)
+            };
+
+            let length = buffer.len();
+
+            if length == 0 {
+                break;
             }
 
+            data.extend_from_slice(buffer);
             self.reader.consume(length);
-            buffer = self.reader.fill_buf()?;
-            length = buffer.len();
+
+            let end = complete(&data, self.unit);
+            self.apply(&data[..end])?;
+            data.drain(..end);
         }
 
+        self.apply(&data)?;
         self.writer.flush()?;
 
         Ok(())
     }
+
+    /// Apply the op to each unit of `data`, writing its output.
+    fn apply(&mut self, data: &[u8]) -> Result<(), std::io::Error> {
+        match self.unit {
+            Unit::Byte => {
+                for &byte in data {
+                    if let Some(c) = (self.op)(char::from(byte).encode_utf8(&mut [0; 4])) {
+                        self.buffering.write(&mut self.writer, &bytes::encode(&c))?;
+                    }
+                }
+
+                return Ok(());
+            },
+            Unit::Char => {
+                for c in data.chars() {
+                    if let Some(c) = (self.op)(c.encode_utf8(&mut [0; 4])) {
+                        self.buffering.write(&mut self.writer, c.as_bytes())?;
+                    }
+                }
+
+                return Ok(());
+            },
+            Unit::Grapheme => ()
+        }
+
+        // FIXME: test & handle non-utf-8 input
+        for b in data.graphemes() {
+            match (self.op)(b) {
+                Some(c) => self.buffering.write(&mut self.writer, c.as_bytes())?,
+                None => continue
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Signal the end of input to the op by passing it the empty string,
+    /// writing anything it returns.
+    pub fn finish(&mut self) -> Result<(), std::io::Error> {
+        if let Some(c) = (self.op)("") {
+            match self.unit {
+                Unit::Byte => self.writer.write_all(&bytes::encode(&c))?,
+                _ => self.writer.write_all(c.as_bytes())?
+            }
+        }
+
+        self.writer.flush()
+    }
+
 }
 
 
+/// The Unicode version of the grapheme segmentation rules, those of the
+/// bstr crate. bstr does not export it, so it is pinned in Cargo.toml to
+/// the release this is the version of, 0.2.14; update both together.
+pub const SEGMENTATION_UNICODE_VERSION: &str = "12.1.0";
+
+
+const HELP: &str = "\
+Usage: tr [OPTION]... SET1 [SET2]
+Translate, squeeze, and/or delete graphemes from standard input,
+writing to standard output. Graphemes are user-perceived characters,
+such as a letter with its accents, or an emoji.
+
+  -c, --complement        use the complement of SET1, translated in order
+                            of code point
+  -C                      use the complement of SET1, translated in order
+                            of collation
+  -d, --delete            delete graphemes in SET1, do not translate
+  -s, --squeeze-repeats   replace each sequence of a repeated grapheme
+                            that is listed in the last specified SET,
+                            with a single occurrence of that grapheme
+  -t, --truncate-set1     first truncate SET1 to length of SET2
+  -E, --extended          allow the set operators -- and && and nested
+                            [...] sets in SET1 and SET2
+  -e                      begin a stage, with options and sets of its
+                            own; stages are applied in order
+      --preset=NAME       use the built-in sets NAME in place of SET1
+                            and SET2
+      --explain           describe how the sets are read, then exit
+      --canonicalize=SET  print the shortest set equivalent to SET,
+                            then exit
+      --check             report each grapheme in SET1 by line and
+                            column, failing if any is found
+      --diff, --preview   show the lines that would change, and how
+      --stats[=freq]      summarize what was done on standard error,
+                            with how often each grapheme of SET1 occurs
+      --fields=LIST       act only on the fields in LIST, e.g. 1,3-
+      --delimiter=C       separate fields with C instead of tab
+      --csv               read fields as CSV, commas and quotes included
+      --json              act only on the string values of JSON input
+      --json-keys         act only on the object keys of JSON input
+      --lines=LIST        act only on the lines in LIST
+      --match=REGEX       act only on lines matching REGEX
+      --between START END act only from each line matching START
+                            through the next line matching END
+  -z, --null-data         end records with NUL, which is left alone;
+                            --lines and the like select records
+      --record-separator=SEP  end records with SEP, a single character,
+                            which is left alone, as with -z
+  -u, --unbuffered        flush output after each write
+      --line-buffered     flush output after each line
+      --follow=FILE       read FILE as it grows, instead of standard
+                            input
+      --bytes             read the sets and the input as bytes
+      --compat=NAME       behave as the tr NAME where they differ: gnu
+                            (the default), bsd or posix
+      --help              display this help and exit
+      --version           output version information and exit
+
+Long options may be abbreviated to any unambiguous prefix, or to any
+prefix of a GNU tr option, which wins over the rest (--d is --delete).
+Options may follow the sets unless POSIXLY_CORRECT is set or --compat
+is bsd or posix. --compat=posix also allows only -c, -C, -d and -s, reads
+characters rather than graphemes, and requires SET2 to be as long as
+SET1 when translating.
+
+SETs are specified as strings of graphemes. Most represent themselves.
+Interpreted sequences are:
+
+  \\NNN            grapheme with octal value NNN (1 to 3 octal digits)
+  \\xHH            grapheme with hex value HH (1 or 2 hex digits)
+  \\uHHHH          grapheme with hex code point HHHH
+  \\u{H...}        grapheme with hex code point H... (1 to 6 hex digits)
+  \\N{NAME}        grapheme with Unicode name NAME
+  \\p{NAME}        all graphemes with Unicode property NAME
+  \\\\              backslash
+  \\a              audible BEL
+  \\b              backspace
+  \\f              form feed
+  \\n              new line
+  \\r              return
+  \\t              horizontal tab
+  \\v              vertical tab
+  CHAR1-CHAR2     all graphemes from CHAR1 to CHAR2 in ascending order
+  [CHAR*]         in SET2, copies of CHAR until length of SET1
+  [CHAR*REPEAT]   REPEAT copies of CHAR, REPEAT octal if starting with 0
+  [:alnum:]       all letters and digits
+  [:alpha:]       all letters
+  [:blank:]       all horizontal whitespace
+  [:cntrl:]       all control graphemes
+  [:digit:]       all digits
+  [:graph:]       all printable graphemes, not including space
+  [:lower:]       all lower case letters
+  [:print:]       all printable graphemes, including space
+  [:punct:]       all punctuation graphemes
+  [:space:]       all horizontal or vertical whitespace
+  [:upper:]       all upper case letters
+  [:xdigit:]      all hexadecimal digits
+  [:PROPERTY:]    all graphemes with Unicode property PROPERTY, e.g.
+                    [:script=Greek:], [:gc=Lu:] or [:emoji:]
+  [=CHAR=]        all graphemes which are equivalent to CHAR
+
+With -E, A--B is the members of A not in B, and A&&B the members of both:
+
+  tr -E -d '[:alpha:]--[aeiou]'
+
+Translation occurs if -d is not given and both SET1 and SET2 appear.
+-t may be used only when translating. SET2 is extended to length of
+SET1 by repeating its last grapheme as necessary. Excess graphemes
+of SET2 are ignored. Squeezing occurs after translation or deletion.
+
+Presets:
+";
+
+
 /// Show program help message
-pub fn show_help() {
-    println!("Usage: tr [OPTION]... SET1 [SET2]");
+pub fn show_help<W: Write>(writer: &mut W) -> Result<(), String> {
+    let presets: String = preset::PRESETS.iter()
+        .map(|preset| format!("  {:<16}{}\n", preset.name, preset.description))
+        .collect();
+
+    write!(writer, "{}{}", HELP, presets).map_err(|e| e.to_string())
 }
 
 
+/// Show program version, with the Unicode versions of the segmentation
+/// rules and character properties used
+pub fn show_version<W: Write>(writer: &mut W) -> Result<(), String> {
+    let (major, minor, update) = unicode_properties::UNICODE_VERSION;
 
-/// Show program version
-pub fn show_version() {
-    println!("tr[ust] 0.9");
+    writeln!(writer, "tr[ust] 0.9").map_err(|e| e.to_string())?;
+    writeln!(writer, "Unicode {} (grapheme segmentation), {}.{}.{} (character properties)",
+             SEGMENTATION_UNICODE_VERSION, major, minor, update).map_err(|e| e.to_string())
+}
+
+
+/// Expand `set` in the syntax selected by `config`.
+pub(crate) fn expand<'a>(config: &Config, set: &'a str) -> Result<Cow<'a, str>, String> {
+    Syntax::of(config).expand(set)
+}
+
+
+/// The warnings about the sets of `config` and of each of its stages.
+pub(crate) fn warnings(config: &Config) -> Vec<String> {
+    let syntax = Syntax::of(config);
+    let mut found = syntax.warnings(&config.set1);
+
+    found.extend(syntax.warnings(&config.set2));
+    found.extend(config.stages.iter().flat_map(warnings));
+    found
+}
+
+
+/// Parse SET1 and SET2 into the graphemes they match.
+///
+/// The sets come from `config.preset` when one is named, otherwise from
+/// parsing `config.set1` and `config.set2`.
+fn member_sets(config: &Config) -> Result<(CharSet, CharSet), String> {
+    match preset::lookup(&config.preset) {
+        Some(preset) => Ok((preset.sources().as_str().into(), preset.targets().as_str().into())),
+        None => {
+            let syntax = Syntax::of(config);
+
+            Ok((syntax.members_set1(&config.set1)?, syntax.members_set2(&config.set2)?))
+        }
+    }
+}
+
+
+/// Combine two ops, passing each grapheme `first` outputs through `second`.
+fn chain(mut first: Op, mut second: Op) -> Op {
+    Box::new(move |b| {
+        // pass a boundary on to both
+        if b.is_empty() {
+            first(b);
+            return second(b);
+        }
+
+        let output = first(b)?;
+        let mut graphemes = output.as_bytes().graphemes();
+
+        match (graphemes.next(), graphemes.next()) {
+            (Some(c), None) => second(c),
+            _ => Some(output.as_bytes().graphemes().filter_map(&mut second).collect())
+        }
+    })
+}
+
+
+/// Combine two ops as `chain` does, but passing the whole output of
+/// `first` for a grapheme through `second` at once, so that a preset's
+/// target of several graphemes is squeezed as one, never within itself.
+fn chain_whole(mut first: Op, mut second: Op) -> Op {
+    Box::new(move |b| {
+        if b.is_empty() {
+            first(b);
+            return second(b);
+        }
+
+        match first(b)? {
+            output if output.is_empty() => Some(output),
+            output => second(&output)
+        }
+    })
 }
 
 
@@ -65,9 +458,110 @@ pub fn show_version() {
 /// translating any grapheme appearing in `config.set1` to the corresponding
 /// target grapheme appearing in `config.set2`, otherwise returning the
 /// original grapheme.
-pub fn translate(config: &Config) -> Box<dyn FnMut(&str) -> Option<String>> {
-    let map = map_charsets(&config.set1, &config.set2);
+///
+/// A preset may translate a grapheme to several graphemes, or to none.
+///
+/// `config.complement`, if `true`, translates the graphemes that do _not_
+/// appear in `config.set1` instead, taken in order of code point, or of
+/// collation with `config.collate`; see `crate::complement::translate`.
+pub fn translate(config: &Config) -> Result<Op, String> {
+    match (config.complement, preset::lookup(&config.preset)) {
+        (true, None) => translate_complement(config),
+        _ => Ok(translate_unmarked(translation(config)?, mark_stripping(config)))
+    }
+}
+
+
+/// The preset of `config`, if it strips the marks of a grapheme before
+/// translating it.
+fn mark_stripping(config: &Config) -> Option<&'static Preset> {
+    preset::lookup(&config.preset).filter(|preset| preset.strip_marks)
+}
+
+
+fn translate_complement(config: &Config) -> Result<Op, String> {
+    let set1 = member_sets(config)?.0;
+    let (before, fill, after) = Syntax::of(config).split_set2(&config.set2)?;
+    let (order, max) = complement_order(config);
+
+    Ok(complement::translate(set1, &before, fill.as_deref(), &after, order, max, config.truncate))
+}
+
+
+/// Pair the members of the complement of SET1 that SET2 names with their
+/// translations, returning the pairs and the translation of every other
+/// member, as `complement::mapping` does.
+pub(crate) fn complement_mapping(config: &Config) -> Result<complement::Mapping, String> {
+    let set1 = member_sets(config)?.0;
+    let (before, fill, after) = Syntax::of(config).split_set2(&config.set2)?;
+    let (order, max) = complement_order(config);
+
+    Ok(complement::mapping(&set1, &before, fill.as_deref(), &after, order, max, config.truncate))
+}
+
+
+/// The order `config` complements SET1 in, and the last character there is
+/// to complement.
+fn complement_order(config: &Config) -> (Order, char) {
+    let order = match config.collate {
+        true => Order::Collation,
+        false => Order::Value
+    };
+
+    // in byte mode, there are only the bytes to complement
+    let max = match config.bytes {
+        true => '\u{ff}',
+        false => char::MAX
+    };
+
+    (order, max)
+}
+
+
+/// Create the mapping from each grapheme `config` translates to its
+/// output.
+pub(crate) fn translation(config: &Config) -> Result<HashMap<String, String>, String> {
+    match preset::lookup(&config.preset) {
+        Some(preset) => Ok(preset.mapping()),
+        None => Syntax::of(config).map(&config.set1, &config.set2, config.truncate)
+    }
+}
+
+
+/// Compose two translations into one, equivalent to applying `first`, then
+/// `second` to each grapheme of its output.
+fn compose(first: HashMap<String, String>, second: HashMap<String, String>) -> HashMap<String, String> {
+    let through_second = |s: &str| -> String {
+        s.as_bytes().graphemes()
+            .map(|g| second.get(g).map_or(g, String::as_str))
+            .collect()
+    };
+
+    let mut map: HashMap<String, String> = first.iter()
+        .map(|(k, v)| (k.to_owned(), through_second(v)))
+        .collect();
+
+    for (k, v) in &second {
+        map.entry(k.to_owned()).or_insert_with(|| v.to_owned());
+    }
 
+    map
+}
+
+
+/// Translate as `map` does each grapheme as `preset`, if given, leaves it
+/// after stripping its marks.
+fn translate_unmarked(map: HashMap<String, String>, preset: Option<&'static Preset>) -> Op {
+    let mut op = translate_map(map);
+
+    match preset {
+        Some(preset) => Box::new(move |b| op(preset.unmarked(b))),
+        None => op
+    }
+}
+
+
+fn translate_map(map: HashMap<String, String>) -> Op {
     Box::new(move |b: &str| {
         match map.get(b) {
             Some(c) => Some(c.to_string()),
@@ -86,20 +580,23 @@ pub fn translate(config: &Config) -> Box<dyn FnMut(&str) -> Option<String>> {
 /// `config.complement`, if `true`, inverts the sense of the test,
 /// returning graphemes that do _not_ appear in `config.set1` and None
 /// otherwise.
-pub fn delete(config: &Config) -> Box<dyn FnMut(&str) -> Option<String>> {
-    let set = parse(&config.set1).as_bytes().graphemes()
-        .map(|c| c.to_string())
-        .collect::<HashSet<_>>();
+pub fn delete(config: &Config) -> Result<Op, String> {
+    let set = member_sets(config)?.0;
+    let preset = mark_stripping(config);
+
+    // a preset that strips marks translates, so deletes, any grapheme it
+    // strips them from
+    let contains = move |b: &str| set.contains(b) || preset.is_some_and(|preset| preset.unmarked(b) != b);
 
     let mut test: Box<dyn FnMut(&str) -> bool> = match config.complement {
-        false => Box::new(move |b| set.contains(b)),
-        true => Box::new(move |b| !set.contains(b))
+        false => Box::new(contains),
+        true => Box::new(move |b| !contains(b))
     };
 
-    Box::new(move |b| match test(b) {
+    Ok(Box::new(move |b| match test(b) {
         true => None,
         false => Some(b.to_string())
-    })
+    }))
 }
 
 
@@ -110,15 +607,14 @@ pub fn delete(config: &Config) -> Box<dyn FnMut(&str) -> Option<String>> {
 ///
 /// `config.complement`, if `true`, inverts the sense of the test,
 /// squeezing graphemes that do _not_ appear in the last defined set.
-pub fn squeeze(config: &Config) -> Box<dyn FnMut(&str) -> Option<String>> {
-    let set = match config.set2.is_empty() {
-        true => &config.set1,
-        false => &config.set2
-    };
+pub fn squeeze(config: &Config) -> Result<Op, String> {
+    let (set1, set2) = member_sets(config)?;
 
-    let set = parse(set).as_bytes().graphemes()
-        .map(|c| c.to_string())
-        .collect::<HashSet<_>>();
+    // a preset squeezes its whole targets, if any
+    let set = match set2.is_empty() && config.preset.is_empty() {
+        true => set1,
+        false => set2
+    };
 
     let mut test: Box<dyn FnMut(&str) -> bool> = match config.complement {
         false => Box::new(move |b| set.contains(b)),
@@ -127,24 +623,149 @@ pub fn squeeze(config: &Config) -> Box<dyn FnMut(&str) -> Option<String>> {
 
     let mut last = String::new();
 
-    Box::new(move |b| match test(b) && b == last {
+    Ok(Box::new(move |b| match test(b) && b == last {
         true => None,
         false => {
             last = b.to_string();
-            return Some(b.to_string());
+            Some(b.to_string())
         }
-    })
+    }))
 }
 
 
-pub fn squeeze_delete(config: &Config) -> Box<dyn FnMut(&str) -> Option<String>> {
-    let mut d = delete(&config);
-    let mut s = squeeze(&config);
+/// Report graphemes according to `config`.
+///
+/// Given a Config, return a function that accepts a Unicode grapheme,
+/// returning a report of its position and code points if it appears in
+/// `config.set1`, and None otherwise:
+///
+///    3:14: U+00A0 NO-BREAK SPACE
+///
+/// Lines and columns count from 1; columns count graphemes, and the empty
+/// string, like a newline, ends a line. Each reported
+/// grapheme is counted in `found`.
+///
+/// `config.complement`, if `true`, inverts the sense of the test,
+/// reporting graphemes that do _not_ appear in `config.set1`.
+pub fn check(config: &Config, found: &Rc<Cell<usize>>) -> Result<Op, String> {
+    let set = member_sets(config)?.0;
+    let complement = config.complement;
+    let found = Rc::clone(found);
+    let (mut line, mut column) = (1, 1);
+
+    Ok(Box::new(move |b| {
+        let report = match !b.is_empty() && set.contains(b) != complement {
+            true => {
+                found.set(found.get() + 1);
+                Some(format!("{}:{}: {}\n", line, column, code_point_names(b)))
+            },
+            false => None
+        };
 
-    Box::new(move |b| match d(b) {
-        Some(b) => s(&b),
-        None => None
-    })
+        match b.is_empty() || b.ends_with('\n') {
+            true => { line += 1; column = 1; },
+            false => column += 1
+        }
+
+        report
+    }))
+}
+
+
+/// List the code points of `g`, then their names:
+/// U+0031 U+20E3 DIGIT ONE + COMBINING ENCLOSING KEYCAP
+fn code_point_names(g: &str) -> String {
+    let code_points: Vec<String> = g.chars().map(|c| format!("U+{:04X}", c as u32)).collect();
+    let names: Vec<String> = g.chars()
+        .map(|c| match unicode_names2::name(c) {
+            Some(name) => name.to_string(),
+            None if c.is_control() => "<control>".to_owned(),
+            None => "<unnamed>".to_owned()
+        })
+        .collect();
+
+    format!("{} {}", code_points.join(" "), names.join(" + "))
+}
+
+
+pub fn squeeze_delete(config: &Config) -> Result<Op, String> {
+    Ok(chain(delete(config)?, squeeze(config)?))
+}
+
+
+/// Translate, then squeeze repeats of graphemes in `config.set2` from the
+/// translated output.
+pub fn translate_squeeze(config: &Config) -> Result<Op, String> {
+    Ok(chain_whole(translate(config)?, squeeze(config)?))
+}
+
+
+/// Create the op `config` calls for, counting its effects in `counter` if
+/// given.
+fn operation(config: &Config, counter: Option<&Counter>) -> Result<Op, String> {
+    let count = |op: Op, effect| match counter {
+        Some(counter) => stats::count(op, counter, effect),
+        None => op
+    };
+
+    let op = if config.delete {
+        count(delete(config)?, Effect::Delete)
+    } else if is_translation(config) {
+        count(translate(config)?, Effect::Translate)
+    } else {
+        return Ok(count(squeeze(config)?, Effect::Squeeze));
+    };
+
+    match config.squeeze {
+        true => Ok(chain_whole(op, count(squeeze(config)?, Effect::Squeeze))),
+        false => Ok(op)
+    }
+}
+
+
+pub(crate) fn is_translation(config: &Config) -> bool {
+    !config.set2.is_empty() || !config.preset.is_empty()
+}
+
+
+/// Create an op applying each of `stages` in turn.
+///
+/// Runs of adjacent stages that only translate are composed into a single
+/// translation table. A stage whose preset strips marks starts a new run,
+/// as they are stripped before the table is looked up.
+fn pipeline(stages: &[Config], counter: Option<&Counter>) -> Result<Op, String> {
+    let translate_map = |(map, preset)| match counter {
+        Some(counter) => stats::count(translate_unmarked(map, preset), counter, Effect::Translate),
+        None => translate_unmarked(map, preset)
+    };
+
+    let mut ops: Vec<Op> = vec![];
+    let mut map: Option<(HashMap<String, String>, Option<&'static Preset>)> = None;
+
+    for stage in stages {
+        if is_translation(stage) && !stage.squeeze && !stage.complement {
+            let next = translation(stage)?;
+            let preset = mark_stripping(stage);
+
+            if preset.is_some() {
+                ops.extend(map.take().map(translate_map));
+            }
+
+            map = Some(match map.take() {
+                Some((map, first)) => (compose(map, next), first),
+                None => (next, preset)
+            });
+        } else {
+            ops.extend(map.take().map(translate_map));
+            ops.push(operation(stage, counter)?);
+        }
+    }
+
+    ops.extend(map.map(translate_map));
+
+    let first = ops.remove(0);
+
+    Ok(ops.into_iter().fold(first, chain))
 }
 
 
@@ -162,34 +783,179 @@ pub fn squeeze_delete(config: &Config) -> Box<dyn FnMut(&str) -> Option<String>>
 /// If `--version` is passed as an option, the program prints version
 /// information and exits.
 ///
-pub fn tr<I, R, W>(args: I, reader: R, writer: &mut W) -> Result<(), String>
+/// If `--explain` is passed as an option, the program describes how it
+/// reads the sets and exits without reading input.
+///
+/// If `--canonicalize SET` is passed as an option, the program prints
+/// the shortest set equivalent to SET and exits without reading input.
+///
+/// If `--check` is passed as an option, the program reports each grapheme
+/// of the input in SET1 instead of transforming the input, failing if it
+/// finds any.
+///
+/// If `--diff` (or `--preview`) is passed as an option, the program shows
+/// each input line that would change, with the changes marked, and writes
+/// nothing else. Changes are coloured unless `NO_COLOR` is set.
+///
+/// If `--stats` is passed as an option, the program prints a summary of
+/// what it did to `errors` after processing.
+///
+/// If `--follow FILE` is passed as an option, the program reads FILE
+/// instead of `reader`, waiting for more data as it grows.
+///
+/// Warnings, such as of an ambiguous octal escape in a set, are written to
+/// `errors` before any input is read.
+///
+pub fn tr<I, R, W, E>(args: I, reader: R, writer: &mut W, errors: &mut E) -> Result<(), String>
 where
     I: IntoIterator,
-    I::Item: AsRef<str>,
+    I::Item: AsRef<OsStr>,
     R: BufRead,
-    W: Write
+    W: Write,
+    E: Write
 {
     let config = parse_args(args)?;
 
+    match config.follow.as_os_str().is_empty() {
+        true => run(&config, reader, writer, errors),
+        false => {
+            let path = &config.follow;
+            let file = Follow::open(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+            run(&config, BufReader::new(file), writer, errors)
+        }
+    }
+}
+
+
+/// Do what `config` calls for, reading from `reader` and writing to
+/// `writer`, and warnings and stats to `errors`.
+fn run<R, W, E>(config: &Config, reader: R, writer: &mut W, errors: &mut E) -> Result<(), String>
+where
+    R: BufRead,
+    W: Write,
+    E: Write
+{
+    // --explain lists the warnings with the rest
+    if !(config.help_requested || config.version_requested || config.explain || config.canonicalize.is_some()) {
+        for warning in warnings(config) {
+            writeln!(errors, "tr: warning: {}", warning).map_err(|e| e.to_string())?;
+        }
+
+        errors.flush().map_err(|e| e.to_string())?;
+    }
+
     if config.help_requested {
-        show_help();
+        show_help(writer)?;
     } else if config.version_requested {
-        show_version();
-    } else {
-        let op = if config.delete {
-            match config.squeeze {
-                true => squeeze_delete(&config),
-                false => delete(&config)
-            }
-        } else if !config.set2.is_empty() {
-            translate(&config)
-        } else {
-            squeeze(&config)
+        show_version(writer)?;
+    } else if config.explain {
+        explain(config, writer)?;
+    } else if let Some(set) = &config.canonicalize {
+        let set = canonicalize(set, Syntax::of(config))?;
+        let set = match config.bytes {
+            true => bytes::encode(&set),
+            false => set.as_bytes().into()
+        };
+
+        writer.write_all(&set).and_then(|_| writer.write_all(b"\n")).map_err(|e| e.to_string())?;
+    } else if config.check {
+        let found = Rc::new(Cell::new(0));
+        let op = select(check(config, &found)?, config, false)?;
+
+        let mut tr = Tr { reader, writer, op, buffering: Buffering::of(config), unit: Unit::of(config) };
+        tr.process().and_then(|_| tr.finish()).map_err(|e| e.to_string())?;
+
+        match found.get() {
+            0 => (),
+            1 => return Err("check failed: 1 grapheme found".to_owned()),
+            n => return Err(format!("check failed: {} graphemes found", n))
+        }
+    } else if config.diff {
+        let counter = Counter::default();
+        let style = match std::env::var_os("NO_COLOR") {
+            Some(_) => Style::Plain,
+            None => Style::Color
         };
 
-        let mut tr = Tr { reader: reader, writer: writer, op: op };
-        tr.process();
+        let op = diff(restrict(config, Some(&counter))?, &counter, style);
+        let op = select(op, config, false)?;
+
+        let mut tr = Tr { reader, writer, op, buffering: Buffering::of(config), unit: Unit::of(config) };
+        tr.process().and_then(|_| tr.finish()).map_err(|e| e.to_string())?;
+    } else {
+        let stats = process(config, reader, writer)?;
+
+        if config.stats {
+            errors.write_all(stats.report().as_bytes())
+                .and_then(|_| errors.flush())
+                .map_err(|e| e.to_string())?;
+        }
     }
 
     Ok(())
 }
+
+
+/// Create the op `config` calls for, with all its stages, divided into
+/// records and restricted to `config.fields` or to JSON strings if
+/// requested, counting its effects in `counter` if given.
+fn restrict(config: &Config, counter: Option<&Counter>) -> Result<Op, String> {
+    let op = match config.stages.is_empty() {
+        true => operation(config, counter)?,
+        false => pipeline(&config.stages, counter)?
+    };
+
+    let op = records(op, config)?;
+
+    match config.fields.is_empty() {
+        _ if config.json || config.json_keys => Ok(json(op, config)),
+        true => Ok(op),
+        false => fields(op, config)
+    }
+}
+
+
+/// Create the op `config` calls for, as `restrict` does, applied only to
+/// the lines `config` selects.
+fn compile(config: &Config, counter: Option<&Counter>) -> Result<Op, String> {
+    select(restrict(config, counter)?, config, true)
+}
+
+
+/// Apply the op `config` calls for to each grapheme read from `reader`,
+/// writing the output to `writer`.
+///
+/// Returns the stats gathered while processing, which are only counted if
+/// `config.stats` is set.
+pub fn process<R, W>(config: &Config, reader: R, writer: &mut W) -> Result<Stats, String>
+where
+    R: BufRead,
+    W: Write
+{
+    let counter = match config.stats {
+        true => Some(Counter::default()),
+        false => None
+    };
+
+    let op = compile(config, counter.as_ref())?;
+
+    let op = match &counter {
+        Some(counter) => {
+            // frequencies are of the members of the first SET1 applied, or of
+            // its complement
+            let first = config.stages.first().unwrap_or(config);
+            let set1 = match config.frequencies {
+                true => Some(member_sets(first)?.0),
+                false => None
+            };
+
+            stats::measure(op, counter, set1, first.complement, config.bytes)
+        },
+        None => op
+    };
+
+    let mut tr = Tr { reader, writer, op, buffering: Buffering::of(config), unit: Unit::of(config) };
+    tr.process().and_then(|_| tr.finish()).map_err(|e| e.to_string())?;
+
+    Ok(counter.map(|counter| counter.take()).unwrap_or_default())
+}
@@ -0,0 +1,193 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+use bstr::ByteSlice;
+use unicode_properties::{GeneralCategoryGroup, UnicodeGeneralCategory};
+
+use crate::command::Op;
+use crate::parser::CharSet;
+
+
+/// The order of the members of a complemented SET1, which decides the
+/// grapheme of SET2 each is translated to.
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub enum Order {
+    /// By code point, as `-c` orders them
+    Value,
+    /// By collation, as `-C` orders them
+    Collation,
+}
+
+
+/// The members of a complemented SET1 that SET2 names, paired with their
+/// graphemes of SET2, and the grapheme every other member becomes, if any.
+pub type Mapping = (Vec<(char, String)>, Option<String>);
+
+
+/// A character's place in `Order::Collation`: its group, its base letter
+/// or itself, its accent, whether it is upper case, and itself.
+pub type Key = (u8, char, char, bool, char);
+
+
+/// The key `Order::Collation` sorts characters by.
+///
+/// A simple, locale-independent approximation of the Unicode collation
+/// order: whitespace and controls come first, then punctuation, symbols,
+/// digits and letters. Letters sort by their lower case base letter, then
+/// by accent, then by case, so that a < A < á < Á < b.
+///
+/// # Examples
+///
+/// ```
+/// use tr::complement::collation_key;
+///
+/// let mut chars = vec!['b', 'Á', 'á', 'A', '1', 'a', '!', ' '];
+/// chars.sort_by_key(|&c| collation_key(c));
+///
+/// assert_eq!(chars, vec![' ', '!', '1', 'a', 'A', 'á', 'Á', 'b']);
+/// ```
+pub fn collation_key(c: char) -> Key {
+    let group = match c.general_category_group() {
+        GeneralCategoryGroup::Separator => 0,
+        _ if c.is_control() => 0,
+        GeneralCategoryGroup::Punctuation => 1,
+        GeneralCategoryGroup::Symbol => 2,
+        GeneralCategoryGroup::Number => 3,
+        GeneralCategoryGroup::Letter | GeneralCategoryGroup::Mark => 4,
+        GeneralCategoryGroup::Other => 5
+    };
+
+    match group {
+        4 => {
+            let lower = c.to_lowercase().next().unwrap_or(c);
+            let base = base_letter(lower).unwrap_or(lower);
+            let accent = if base == lower { '\0' } else { lower };
+
+            (group, base, accent, lower != c, c)
+        },
+        _ => (group, c, '\0', false, c)
+    }
+}
+
+
+/// The letter `c` is written with an accent on, if any, found from its
+/// name: the base of LATIN SMALL LETTER E WITH ACUTE is
+/// LATIN SMALL LETTER E.
+fn base_letter(c: char) -> Option<char> {
+    // only the alphabets, not the ideographs, have accented letters
+    if c > '\u{2fff}' {
+        return None;
+    }
+
+    let name = unicode_names2::name(c)?.to_string();
+    let base = &name[..name.find(" WITH ")?];
+
+    unicode_names2::character(base)
+}
+
+
+/// The first `n` characters up to `max` in `order` that are not in `set`,
+/// and the last `m`, last first.
+fn ends(set: &CharSet, order: Order, max: char, n: usize, m: usize) -> (Vec<char>, Vec<char>) {
+    let outside = |c: &char| !set.contains(c.encode_utf8(&mut [0; 4]));
+    let chars = || ('\0'..=max).filter(outside);
+
+    match order {
+        Order::Value => (chars().take(n).collect(), chars().rev().take(m).collect()),
+        Order::Collation => {
+            // keep the n smallest keys, scanning up until none to come can
+            // be smaller: separators and controls sort first, by code point
+            let mut first = BinaryHeap::new();
+
+            for c in chars() {
+                if first.len() == n && first.peek().is_none_or(|max: &Key| max.0 == 0 && max.1 < c) {
+                    break;
+                }
+
+                first.push(collation_key(c));
+
+                if first.len() > n {
+                    first.pop();
+                }
+            }
+
+            // and the m largest, scanning down until none to come can be
+            // larger: Other, mostly unassigned and private use code points,
+            // sorts last, by code point
+            let mut last = BinaryHeap::new();
+
+            for c in chars().rev() {
+                if last.len() == m && last.peek().is_none_or(|Reverse(min): &Reverse<Key>| min.0 == 5 && min.1 > c) {
+                    break;
+                }
+
+                last.push(Reverse(collation_key(c)));
+
+                if last.len() > m {
+                    last.pop();
+                }
+            }
+
+            (first.into_sorted_vec().into_iter().map(|key| key.4).collect(),
+             last.into_sorted_vec().into_iter().map(|Reverse(key)| key.4).collect())
+        }
+    }
+}
+
+
+/// Pair the members of the complement of `set1` that SET2 names with its
+/// graphemes, in `order`, returning the pairs and the grapheme every
+/// other member is translated to, if any.
+///
+/// The pairs for the start of SET2 come first, then those for its end,
+/// where a member may appear again if the complement is shorter than
+/// SET2; its first pair applies. The arguments are as for `translate`.
+pub fn mapping(set1: &CharSet, before: &str, fill: Option<&str>, after: &str,
+               order: Order, max: char, truncate: bool) -> Mapping {
+    let before: Vec<&str> = before.as_bytes().graphemes().collect();
+    let after: Vec<&str> = after.as_bytes().graphemes().collect();
+
+    let (first, last) = ends(set1, order, max, before.len(), after.len());
+
+    let mut pairs: Vec<(char, String)> = first.into_iter().zip(&before).map(|(c, g)| (c, g.to_string())).collect();
+
+    pairs.extend(last.into_iter().zip(after.iter().rev()).rev().map(|(c, g)| (c, g.to_string())));
+
+    let rest = match fill {
+        Some(fill) => Some(fill.to_owned()),
+        None if truncate => None,
+        None => before.last().map(|g| g.to_string())
+    };
+
+    (pairs, rest)
+}
+
+
+/// Translate the complement of `set1`, the characters up to `max` that
+/// are not in it, taken in `order`, onto SET2.
+///
+/// SET2 is given as the graphemes `before` a repeat that fills, the
+/// grapheme it repeats, if any, and the graphemes `after` it. Without a
+/// fill, characters beyond the end of SET2 are translated to its last
+/// grapheme, unless `truncate` is `true`, in which case they are left
+/// alone. Graphemes of several characters, which have no place in the
+/// order, are treated like those characters beyond the end of SET2.
+pub fn translate(set1: CharSet, before: &str, fill: Option<&str>, after: &str,
+                 order: Order, max: char, truncate: bool) -> Op {
+    let (pairs, rest) = mapping(&set1, before, fill, after, order, max, truncate);
+
+    // collected last first, so that the first pair for a member applies
+    let map: HashMap<String, String> = pairs.into_iter().rev().map(|(c, g)| (c.to_string(), g)).collect();
+
+    Box::new(move |b| {
+        if b.is_empty() || set1.contains(b) {
+            return Some(b.to_owned());
+        }
+
+        match (map.get(b), &rest) {
+            (Some(g), _) => Some(g.to_owned()),
+            (None, Some(g)) => Some(g.to_owned()),
+            (None, None) => Some(b.to_owned())
+        }
+    })
+}
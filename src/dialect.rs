@@ -0,0 +1,65 @@
+/// The tr to follow where implementations differ, as selected with
+/// `--compat`:
+///
+/// ```text
+///                            gnu          bsd          posix
+///    options after the sets  allowed      -            -
+///    options beyond -cCds    allowed      allowed      rejected
+///    \NNN above \377         \NN then N   up to \777   rejected
+///    [c*N] counts            0N octal     0N octal,    0N octal
+///                                         0xN hex
+///    SET2 shorter than SET1  padded       padded       rejected
+///    \x, \u, \N, \p escapes  allowed      allowed      rejected
+///    [:PROPERTY:] classes    allowed      allowed      -
+///    sets and input read as  graphemes    graphemes    characters
+/// ```
+///
+/// In every dialect, a `[c*]` repeat may only appear in SET2, where a
+/// repeat without a count, or with a count of 0, extends SET2 to the
+/// length of SET1.
+#[derive(Debug,Clone,Copy,PartialEq,Default)]
+pub enum Dialect {
+    #[default]
+    Gnu,
+    Bsd,
+    Posix,
+}
+
+
+impl Dialect {
+    /// Find the dialect called `name`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tr::dialect::Dialect;
+    ///
+    /// assert_eq!(Dialect::parse("bsd"), Ok(Dialect::Bsd));
+    /// assert!(Dialect::parse("sysv").is_err());
+    /// ```
+    pub fn parse(name: &str) -> Result<Dialect, String> {
+        match name {
+            "gnu" => Ok(Dialect::Gnu),
+            "bsd" => Ok(Dialect::Bsd),
+            "posix" => Ok(Dialect::Posix),
+            _ => Err(format!("invalid argument ‘{}’ for ‘--compat’; valid arguments are ‘gnu’, ‘bsd’ and ‘posix’",
+                             name))
+        }
+    }
+
+    /// Whether options may follow the sets, as getopt_long permits.
+    pub fn permutes(self) -> bool {
+        self == Dialect::Gnu
+    }
+
+    /// The repeat count written `count` in `[c*count]`.
+    pub fn repeat_count(self, count: &str) -> Option<usize> {
+        match (self, count) {
+            (Dialect::Bsd, _) if count.starts_with("0x") || count.starts_with("0X") => {
+                usize::from_str_radix(&count[2..], 16).ok()
+            },
+            (_, _) if count.starts_with('0') => usize::from_str_radix(count, 8).ok(),
+            (_, _) => count.parse().ok()
+        }
+    }
+}
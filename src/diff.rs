@@ -0,0 +1,94 @@
+use crate::command::Op;
+use crate::explain::visible;
+use crate::stats::Counter;
+
+
+/// How to mark the changes in a line.
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub enum Style {
+    /// ANSI colours: deleted in red, inserted in green, squeezed in yellow
+    Color,
+    /// Brackets: [-deleted-]{+inserted+}[~squeezed~]
+    Plain,
+}
+
+
+impl Style {
+    fn deleted(self, g: &str) -> String {
+        match self {
+            Style::Color => format!("\x1b[9;31m{}\x1b[0m", visible(g)),
+            Style::Plain => format!("[-{}-]", visible(g)),
+        }
+    }
+
+    fn inserted(self, g: &str) -> String {
+        match self {
+            Style::Color => format!("\x1b[32m{}\x1b[0m", visible(g)),
+            Style::Plain => format!("{{+{}+}}", visible(g)),
+        }
+    }
+
+    fn squeezed(self, g: &str) -> String {
+        match self {
+            Style::Color => format!("\x1b[9;33m{}\x1b[0m", visible(g)),
+            Style::Plain => format!("[~{}~]", visible(g)),
+        }
+    }
+}
+
+
+/// Wrap `op` to preview its changes instead of making them.
+///
+/// Returns a function that accepts a Unicode grapheme, passes it to `op`,
+/// and, at the end of each input line that `op` changes, returns the line
+/// with its number and the changes marked in `style`:
+///
+///    3: tab[-\t-]{+ +}separated
+///
+/// `op` must count its effects in `counter`, so that squeezed graphemes
+/// can be told apart from deleted ones. The empty string signals the end
+/// of input, flushing a final line that lacks a newline.
+pub fn diff(mut op: Op, counter: &Counter, style: Style) -> Op {
+    let counter = Counter::clone(counter);
+    let mut line = String::new();
+    let mut number = 1;
+    let mut changed = false;
+
+    Box::new(move |b| {
+        if !b.is_empty() {
+            let squeezed = counter.borrow().squeezed;
+
+            match op(b) {
+                Some(c) if c == b => line.push_str(b.trim_end_matches(&['\r', '\n'][..])),
+                Some(c) => {
+                    line.push_str(&style.deleted(b));
+                    line.push_str(&style.inserted(&c));
+                    changed = true;
+                },
+                None if counter.borrow().squeezed > squeezed => {
+                    line.push_str(&style.squeezed(b));
+                    changed = true;
+                },
+                None => {
+                    line.push_str(&style.deleted(b));
+                    changed = true;
+                }
+            }
+
+            if !b.ends_with('\n') {
+                return None;
+            }
+        }
+
+        let report = match changed {
+            true => Some(format!("{}: {}\n", number, line)),
+            false => None
+        };
+
+        line.clear();
+        number += 1;
+        changed = false;
+
+        report
+    })
+}
@@ -0,0 +1,229 @@
+use std::collections::HashMap;
+use std::io::Write;
+
+use bstr::ByteSlice;
+use unicode_properties::{GeneralCategoryGroup, UnicodeGeneralCategory};
+
+use crate::arg_parser::Config;
+use crate::command::{complement_mapping, expand, is_translation, translation};
+use crate::lex::{tokenize, tokenize_extended};
+use crate::preset;
+
+
+/// The most graphemes of a set to list before eliding the rest.
+const MAX_LISTED: usize = 64;
+
+
+/// Write an account of what `config` will do to `writer`: the tokens and
+/// graphemes of each set, the mapping or membership that results, and
+/// warnings about anything likely to be a mistake.
+pub fn explain<W: Write>(config: &Config, writer: &mut W) -> Result<(), String> {
+    let mut lines = vec![];
+
+    match config.stages.is_empty() {
+        true => explain_stage(config, &mut lines)?,
+        false => for (i, stage) in config.stages.iter().enumerate() {
+            lines.push(format!("stage {}:", i + 1));
+            explain_stage(stage, &mut lines)?;
+        }
+    }
+
+    for line in lines {
+        writeln!(writer, "{}", line).map_err(|e| e.to_string())?;
+    }
+
+    Ok(())
+}
+
+
+fn explain_stage(config: &Config, lines: &mut Vec<String>) -> Result<(), String> {
+    let action = match (config.delete, is_translation(config), config.squeeze) {
+        (true, _, true) => "delete SET1, then squeeze repeats of SET2",
+        (true, _, false) => "delete SET1",
+        (false, true, true) => "translate SET1 to SET2, then squeeze repeats of SET2",
+        (false, true, false) => "translate SET1 to SET2",
+        (false, false, _) => "squeeze repeats of SET1",
+    };
+
+    lines.push(format!("action: {}", action));
+
+    if config.complement {
+        let order = match config.collate {
+            true => "collation",
+            false => "code point"
+        };
+
+        lines.push(format!("complement: SET1 stands for every grapheme not in it, in order of {}", order));
+    }
+
+    let (set1, set2) = match preset::lookup(&config.preset) {
+        Some(preset) => {
+            lines.push(format!("preset: {} ({})", preset.name, preset.description));
+            (preset.sources(), preset.targets())
+        },
+        None => (explain_set("SET1", &config.set1, config, lines)?,
+                 explain_set("SET2", &config.set2, config, lines)?)
+    };
+
+    if is_translation(config) && !config.delete && config.complement && config.preset.is_empty() {
+        explain_complement_mapping(config, lines)?;
+    } else if is_translation(config) && !config.delete {
+        explain_mapping(config, &set1, &set2, lines)?;
+    } else if config.delete {
+        lines.push(format!("deleted: {}", listing(&set1)));
+    }
+
+    if config.squeeze {
+        let squeezed = match set2.is_empty() && config.preset.is_empty() {
+            true => &set1,
+            false => &set2
+        };
+
+        lines.push(format!("squeezed: {}", listing(squeezed)));
+    }
+
+    Ok(())
+}
+
+
+/// Explain how `set` is read, returning its expansion.
+fn explain_set(label: &str, set: &str, config: &Config, lines: &mut Vec<String>) -> Result<String, String> {
+    if set.is_empty() {
+        return Ok(String::new());
+    }
+
+    let tokens = match config.extended {
+        true => tokenize_extended(set),
+        false => tokenize(set)
+    };
+
+    let mut tokens = tokens.dialect(config.dialect);
+    let listed: Vec<String> = tokens.by_ref()
+        .map(|t| format!("{:?} ‘{}’", t.token_type, visible(&t.token)))
+        .collect();
+
+    let expanded = expand(config, set)?.into_owned();
+
+    lines.push(format!("{}: ‘{}’", label, set));
+    lines.push(format!("  tokens: {}", listed.join(", ")));
+    lines.push(format!("  graphemes: {}", listing(&expanded)));
+    lines.extend(tokens.warnings().iter().map(|w| format!("warning: {}", w)));
+
+    Ok(expanded)
+}
+
+
+fn explain_mapping(config: &Config, set1: &str, set2: &str, lines: &mut Vec<String>) -> Result<(), String> {
+    let map = translation(config)?;
+    let set1: Vec<&str> = set1.as_bytes().graphemes().collect();
+    let set2: Vec<&str> = set2.as_bytes().graphemes().collect();
+
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    let mut order = vec![];
+
+    for &g in &set1 {
+        let count = counts.entry(g).or_insert(0);
+
+        if *count == 0 {
+            order.push(g);
+        }
+
+        *count += 1;
+    }
+
+    lines.push("mapping:".to_owned());
+
+    for g in order.iter().filter(|g| map.contains_key(**g)) {
+        let target = match map[*g].as_str() {
+            "" => "(nothing)".to_owned(),
+            target => describe(target)
+        };
+
+        lines.push(format!("  {} → {}", describe(g), target));
+    }
+
+    if config.preset.is_empty() {
+        for g in order.iter().filter(|g| counts[**g] > 1) {
+            lines.push(format!("warning: ‘{}’ appears {} times in SET1; the last mapping applies",
+                               visible(g), counts[g]));
+        }
+
+        match (set1.len(), set2.len()) {
+            (n1, n2) if n1 > n2 && config.truncate => {
+                lines.push(format!("warning: SET1 truncated from {} to {} graphemes", n1, n2));
+            },
+            (n1, n2) if n1 > n2 && n2 > 0 => {
+                lines.push(format!("warning: SET2 padded from {} to {} graphemes by repeating ‘{}’",
+                                   n2, n1, visible(set2[n2 - 1])));
+            },
+            (n1, n2) if n2 > n1 => {
+                lines.push(format!("warning: SET2 is longer than SET1 by {}; the excess is unused", n2 - n1));
+            },
+            _ => ()
+        }
+    }
+
+    Ok(())
+}
+
+
+/// Explain the translation of the complement of SET1: the members SET2
+/// names, then what becomes of the rest.
+fn explain_complement_mapping(config: &Config, lines: &mut Vec<String>) -> Result<(), String> {
+    let (pairs, rest) = complement_mapping(config)?;
+
+    lines.push("mapping:".to_owned());
+
+    for (c, g) in &pairs {
+        lines.push(format!("  {} → {}", describe(&c.to_string()), describe(g)));
+    }
+
+    let rest = match rest {
+        Some(g) => describe(&g),
+        None => "(unchanged)".to_owned()
+    };
+
+    lines.push(format!("  every other grapheme not in SET1 → {}", rest));
+
+    Ok(())
+}
+
+
+/// List the graphemes of `s` with their code points, eliding all past
+/// the first `MAX_LISTED`.
+fn listing(s: &str) -> String {
+    let graphemes: Vec<&str> = s.as_bytes().graphemes().collect();
+    let mut listed: Vec<String> = graphemes.iter().take(MAX_LISTED).map(|g| describe(g)).collect();
+
+    if graphemes.len() > MAX_LISTED {
+        listed.push(format!("… and {} more", graphemes.len() - MAX_LISTED));
+    }
+
+    format!("({}) {}", graphemes.len(), listed.join(", "))
+}
+
+
+/// Quote `g`, followed by its code points: ‘é’ U+0065 U+0301
+pub(crate) fn describe(g: &str) -> String {
+    let code_points: Vec<String> = g.chars().map(|c| format!("U+{:04X}", c as u32)).collect();
+
+    format!("‘{}’ {}", visible(g), code_points.join(" "))
+}
+
+
+/// Replace characters that would not show up on a terminal with escapes.
+pub(crate) fn visible(s: &str) -> String {
+    s.chars().map(|c| match c {
+        '\\' => "\\\\".to_owned(),
+        '\n' => "\\n".to_owned(),
+        '\r' => "\\r".to_owned(),
+        '\t' => "\\t".to_owned(),
+        ' ' => " ".to_owned(),
+        c => match c.general_category_group() {
+            GeneralCategoryGroup::Other | GeneralCategoryGroup::Separator => {
+                format!("\\u{{{:x}}}", c as u32)
+            },
+            _ => c.to_string()
+        }
+    }).collect()
+}
@@ -0,0 +1,138 @@
+use bstr::ByteSlice;
+
+use crate::arg_parser::Config;
+use crate::command::Op;
+
+
+/// A list of fields, as given to `--fields`: numbers and ranges separated
+/// by commas, counting from 1.
+///
+///    N      field N
+///    N-M    fields N to M
+///    N-     field N and every one after it
+///    -M     fields 1 to M
+#[derive(Debug,PartialEq)]
+pub struct Fields(Vec<(usize, usize)>);
+
+
+impl Fields {
+    /// Parse a field list.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let fields = tr::fields::Fields::parse("2,4-").unwrap();
+    ///
+    /// assert!(fields.contains(2));
+    /// assert!(!fields.contains(3));
+    /// assert!(fields.contains(40));
+    /// ```
+    pub fn parse(list: &str) -> Result<Fields, String> {
+        let number = |s: &str, default| match s {
+            "" => Ok(default),
+            s => match s.parse::<usize>() {
+                Ok(0) => Err("fields are numbered from 1".to_owned()),
+                Ok(n) => Ok(n),
+                Err(_) => Err(format!("invalid field value ‘{}’", s))
+            }
+        };
+
+        list.split(',')
+            .map(|range| {
+                let (first, last) = match range.find('-') {
+                    _ if range.is_empty() || range == "-" => {
+                        return Err(format!("invalid field range ‘{}’", range));
+                    },
+                    Some(i) => (number(&range[..i], 1)?, number(&range[i + 1..], usize::MAX)?),
+                    None => {
+                        let n = number(range, 1)?;
+                        (n, n)
+                    }
+                };
+
+                match first <= last {
+                    true => Ok((first, last)),
+                    false => Err("invalid decreasing range".to_owned())
+                }
+            })
+            .collect::<Result<_, _>>()
+            .map(Fields)
+    }
+
+    /// Test whether field `n` is in the list.
+    pub fn contains(&self, n: usize) -> bool {
+        self.0.iter().any(|&(first, last)| first <= n && n <= last)
+    }
+}
+
+
+/// Restrict `op` to the fields `config.fields` of each line.
+///
+/// Fields are separated by `config.delimiter`, a single grapheme, or by a
+/// tab if none is given. Graphemes of the selected fields are passed to
+/// `op`; delimiters, line ends and the graphemes of every other field are
+/// written unchanged. The end of each field is a boundary passed to `op`;
+/// see `crate::command::Op`.
+///
+/// With `config.csv`, the delimiter defaults to a comma, and fields may be
+/// quoted as in RFC 4180: a quoted field may contain delimiters, line ends
+/// and doubled quotes. The quotes themselves are written unchanged.
+pub fn fields(mut op: Op, config: &Config) -> Result<Op, String> {
+    let fields = Fields::parse(&config.fields)?;
+    let csv = config.csv;
+
+    let delimiter = match (config.delimiter.as_str(), csv) {
+        ("", false) => "\t".to_owned(),
+        ("", true) => ",".to_owned(),
+        (d, _) if d.as_bytes().graphemes().count() == 1 => d.to_owned(),
+        _ => return Err("the delimiter must be a single character".to_owned())
+    };
+
+    let mut field = 1;
+    let mut field_start = true;
+    let mut quoted = false;
+    let mut closed = false;
+
+    Ok(Box::new(move |b| {
+        let selected = fields.contains(field);
+
+        if csv && b == "\"" && (field_start || quoted || closed) {
+            // opening or closing quote; a doubled quote closes and reopens
+            quoted = !quoted;
+            closed = !quoted;
+            field_start = false;
+
+            return Some(b.to_owned());
+        }
+
+        closed = false;
+
+        if quoted {
+            return match selected {
+                true => op(b),
+                false => Some(b.to_owned())
+            };
+        }
+
+        if b == delimiter || b.ends_with('\n') || b.is_empty() {
+            if selected {
+                op("");
+            }
+
+            field = match b == delimiter {
+                true => field + 1,
+                false => 1
+            };
+            field_start = true;
+
+            return Some(b.to_owned());
+        }
+
+        field_start = false;
+
+        match selected {
+            true => op(b),
+            false => Some(b.to_owned())
+        }
+    }))
+}
@@ -0,0 +1,109 @@
+use std::fs::{File, Metadata};
+use std::io::{ErrorKind, Read, Seek, SeekFrom};
+use std::path::PathBuf;
+use std::time::Duration;
+
+
+/// How long to wait before looking for more data.
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+
+/// A reader that follows a growing file, like `tail -f`.
+///
+/// Reads the file from the start, then waits for data to be appended
+/// rather than reporting the end of the file. If the file is truncated,
+/// reading starts again from its beginning; if it is replaced, e.g. by log
+/// rotation, the new file is read from its beginning once the old one is
+/// exhausted.
+///
+/// The first time a read finds no new data after some was read, it fails
+/// with `ErrorKind::WouldBlock` before waiting, so that the caller can act
+/// on what it holds, such as a last line with no newline.
+pub struct Follow {
+    path: PathBuf,
+    file: File,
+    position: u64,
+    /// Whether the caller was told there is no new data since the last read
+    idle: bool,
+}
+
+
+impl Follow {
+    pub fn open<P: Into<PathBuf>>(path: P) -> std::io::Result<Follow> {
+        let path = path.into();
+        let file = File::open(&path)?;
+
+        Ok(Follow { path, file, position: 0, idle: true })
+    }
+
+    /// Prepare to read anew if the file was truncated or replaced, returning
+    /// whether it was.
+    fn reopen(&mut self) -> std::io::Result<bool> {
+        // the path may be missing for a moment while the file is replaced
+        let current = match std::fs::metadata(&self.path) {
+            Ok(metadata) => metadata,
+            Err(_) => return Ok(false)
+        };
+
+        let metadata = self.file.metadata()?;
+
+        if !same_file(&metadata, &current) {
+            if let Ok(file) = File::open(&self.path) {
+                self.file = file;
+                self.position = 0;
+                return Ok(true);
+            }
+        } else if metadata.len() < self.position {
+            self.file.seek(SeekFrom::Start(0))?;
+            self.position = 0;
+            return Ok(true);
+        }
+
+        Ok(false)
+    }
+}
+
+
+impl Read for Follow {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        loop {
+            let length = self.file.read(buf)?;
+
+            if length > 0 {
+                self.position += length as u64;
+                self.idle = false;
+                return Ok(length);
+            }
+
+            if self.reopen()? {
+                continue;
+            }
+
+            if !self.idle {
+                self.idle = true;
+                return Err(std::io::Error::new(ErrorKind::WouldBlock, "no new data"));
+            }
+
+            std::thread::sleep(POLL_INTERVAL);
+        }
+    }
+}
+
+
+#[cfg(unix)]
+fn same_file(a: &Metadata, b: &Metadata) -> bool {
+    use std::os::unix::fs::MetadataExt;
+
+    (a.dev(), a.ino()) == (b.dev(), b.ino())
+}
+
+
+// without inode numbers, only truncation can be detected
+#[cfg(not(unix))]
+fn same_file(_a: &Metadata, _b: &Metadata) -> bool {
+    true
+}
@@ -0,0 +1,238 @@
+use crate::arg_parser::Config;
+use crate::command::Op;
+
+
+#[derive(Debug,PartialEq)]
+enum Container {
+    Array,
+    /// An object, and whether a key is expected next
+    Object(bool),
+}
+
+
+/// Where the scanner is within the JSON text.
+#[derive(Debug,PartialEq)]
+enum State {
+    Outside,
+    /// Within a string, and whether the string is selected for the op
+    String(bool),
+    /// Within an escape sequence in a string; the text so far, starting at
+    /// the backslash
+    Escape(bool, String),
+}
+
+
+struct Scanner {
+    op: Op,
+    values: bool,
+    keys: bool,
+    containers: Vec<Container>,
+    state: State,
+    /// A high surrogate escape awaiting its low surrogate
+    high: Option<(String, u32)>,
+}
+
+
+impl Scanner {
+    fn feed(&mut self, g: &str, output: &mut String) {
+        let mut chars = g.chars();
+
+        // a quote or backslash may begin a grapheme with a combining mark
+        match chars.next() {
+            Some(c @ '"') | Some(c @ '\\') if g.len() > 1 => {
+                self.feed(&g[..c.len_utf8()], output);
+                self.feed(chars.as_str(), output);
+                return;
+            },
+            _ => ()
+        }
+
+        match std::mem::replace(&mut self.state, State::Outside) {
+            State::Outside => {
+                self.state = self.structure(g);
+                output.push_str(g);
+            },
+            State::String(selected) => match g {
+                "\"" => {
+                    self.flush_high(output);
+                    (self.op)("");
+                    output.push_str(g);
+                },
+                "\\" => self.state = State::Escape(selected, g.to_owned()),
+                g => {
+                    self.flush_high(output);
+                    self.state = State::String(selected);
+                    self.translate(g, g, selected, output);
+                }
+            },
+            State::Escape(selected, mut escape) => {
+                escape.push_str(g);
+                self.state = State::String(selected);
+
+                match unescape(&escape) {
+                    Unescaped::Incomplete => self.state = State::Escape(selected, escape),
+                    Unescaped::Char(c) => {
+                        self.flush_high(output);
+                        self.translate(&c.to_string(), &escape, selected, output);
+                    },
+                    Unescaped::High(high) => {
+                        self.flush_high(output);
+                        self.high = Some((escape, high));
+                    },
+                    Unescaped::Low(low) => match self.high.take() {
+                        Some((mut raw, high)) => {
+                            let c = 0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00);
+                            let c = std::char::from_u32(c).expect("surrogate pair out of range");
+
+                            raw.push_str(&escape);
+                            self.translate(&c.to_string(), &raw, selected, output);
+                        },
+                        None => output.push_str(&escape)
+                    },
+                    Unescaped::Invalid => {
+                        self.flush_high(output);
+                        output.push_str(&escape);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Follow the structure of the document outside strings, returning the
+    /// state after `g`.
+    fn structure(&mut self, g: &str) -> State {
+        match (g, self.containers.last_mut()) {
+            ("{", _) => self.containers.push(Container::Object(true)),
+            ("[", _) => self.containers.push(Container::Array),
+            ("}", _) | ("]", _) => { self.containers.pop(); },
+            (":", Some(Container::Object(key))) => *key = false,
+            (",", Some(Container::Object(key))) => *key = true,
+            ("\"", container) => {
+                let selected = match container {
+                    Some(Container::Object(true)) => self.keys,
+                    _ => self.values
+                };
+
+                return State::String(selected);
+            },
+            _ => ()
+        }
+
+        State::Outside
+    }
+
+    /// Write the op's output for `g`, which appears in the input as `raw`.
+    fn translate(&mut self, g: &str, raw: &str, selected: bool, output: &mut String) {
+        match selected {
+            true => match (self.op)(g) {
+                Some(c) if c == g => output.push_str(raw),
+                Some(c) => output.push_str(&escape(&c)),
+                None => ()
+            },
+            false => output.push_str(raw)
+        }
+    }
+
+    /// Write a high surrogate escape that no low surrogate followed.
+    fn flush_high(&mut self, output: &mut String) {
+        if let Some((raw, _)) = self.high.take() {
+            output.push_str(&raw);
+        }
+    }
+}
+
+
+enum Unescaped {
+    Incomplete,
+    Char(char),
+    High(u32),
+    Low(u32),
+    Invalid,
+}
+
+
+/// Decode a JSON escape sequence, given as the text from its backslash.
+fn unescape(escape: &str) -> Unescaped {
+    let c = match &escape[1..] {
+        "\"" => '"',
+        "\\" => '\\',
+        "/" => '/',
+        "b" => '\u{8}',
+        "f" => '\u{c}',
+        "n" => '\n',
+        "r" => '\r',
+        "t" => '\t',
+        s if s.starts_with('u') && s[1..].chars().all(|c| c.is_ascii_hexdigit()) => {
+            if s.len() < 5 {
+                return Unescaped::Incomplete;
+            }
+
+            return match u32::from_str_radix(&s[1..], 16).unwrap() {
+                n @ 0xd800..=0xdbff => Unescaped::High(n),
+                n @ 0xdc00..=0xdfff => Unescaped::Low(n),
+                n => Unescaped::Char(std::char::from_u32(n).unwrap())
+            };
+        },
+        _ => return Unescaped::Invalid
+    };
+
+    Unescaped::Char(c)
+}
+
+
+/// Escape `s` for use within a JSON string.
+fn escape(s: &str) -> String {
+    s.chars().map(|c| match c {
+        '"' => "\\\"".to_owned(),
+        '\\' => "\\\\".to_owned(),
+        '\n' => "\\n".to_owned(),
+        '\r' => "\\r".to_owned(),
+        '\t' => "\\t".to_owned(),
+        '\u{8}' => "\\b".to_owned(),
+        '\u{c}' => "\\f".to_owned(),
+        c if (c as u32) < 0x20 => format!("\\u{:04x}", c as u32),
+        c => c.to_string()
+    }).collect()
+}
+
+
+/// Restrict `op` to the string literals of a JSON document: to values with
+/// `config.json`, and to object keys with `config.json_keys`.
+///
+/// The op sees each string's characters as decoded from any escapes, and
+/// its output is escaped as needed; an escape the op leaves unchanged is
+/// written as it was. Everything outside the selected strings, including
+/// their quotes, is written unchanged. Each string ends with the empty
+/// string passed to `op`.
+///
+/// # Examples
+///
+/// ```
+/// use tr::arg_parser::parse_args;
+///
+/// let config = parse_args(&["tr", "--json", "a-z", "A-Z"]).unwrap();
+/// let mut op = tr::json::json(tr::command::translate(&config).unwrap(), &config);
+///
+/// let output: String = r#"{"key": "café"}"#.chars()
+///     .filter_map(|c| op(&c.to_string()))
+///     .collect();
+///
+/// assert_eq!(output, r#"{"key": "CAFé"}"#);
+/// ```
+pub fn json(op: Op, config: &Config) -> Op {
+    let mut scanner = Scanner {
+        op,
+        values: config.json,
+        keys: config.json_keys,
+        containers: vec![],
+        state: State::Outside,
+        high: None,
+    };
+
+    Box::new(move |b| {
+        let mut output = String::new();
+
+        scanner.feed(b, &mut output);
+        Some(output)
+    })
+}
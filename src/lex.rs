@@ -1,13 +1,33 @@
-use std::mem::replace;
+use std::borrow::Cow;
+use std::collections::VecDeque;
 
 use bstr::{ByteSlice};
 
+use crate::dialect::Dialect;
+use crate::property::Property;
 
+
+/// The longest name `\N{...}` or `\p{...}` is searched for, in characters,
+/// so that an unclosed brace does not send each escape to the end of the
+/// set.
+const MAX_NAME: usize = 128;
+
+
+/// An iterator over the tokens of a set.
+///
+/// Scans the set once, from start to end, holding at most the few tokens
+/// found in one step, such as a literal run and the class after it.
 #[derive(Debug)]
 pub struct Lexer<'a> {
-    s: &'a str,
-    tokens: Vec<Token>,
-    state: State,
+    source: &'a str,
+    /// The byte offset and column in `source` where the next token begins
+    position: usize,
+    column: usize,
+    tokens: VecDeque<Token<'a>>,
+    extended: bool,
+    dialect: Dialect,
+    depth: usize,
+    warnings: Vec<String>,
 }
 
 
@@ -18,164 +38,270 @@ pub enum TokenType {
     CharRepeat,
     CharClass,
     Equivalence,
+    /// Malformed syntax, such as an unknown character name or class; the
+    /// token holds the offending text.
+    Invalid,
+    /// In extended syntax, a set operator: `--` (difference) or `&&`
+    /// (intersection).
+    SetOperator,
+    /// In extended syntax, the `[` opening a nested set.
+    SetStart,
+    /// In extended syntax, the `]` closing a nested set.
+    SetEnd,
 }
 
 
+/// A token of a set.
+///
+/// The text of the token borrows from the set, except where an escape
+/// sequence has been replaced by the character it stands for.
 #[derive(Debug,PartialEq)]
-enum State {
-    ScanLiteral,
-    InterpretBackslash,
-    InterpretBackslashOctal,
-    RangePending,
+pub struct Token<'a> {
+    pub token_type: TokenType,
+    pub token: Cow<'a, str>,
+    /// Where the token was read from in the set
+    pub span: Span,
 }
 
 
-#[derive(Debug,PartialEq)]
-pub struct Token {
-    pub token_type: TokenType,
-    pub token: String,
+/// Where a token lies in the set it was read from: its start and end as
+/// byte offsets, and as columns counted in graphemes, both from 0.
+///
+/// # Examples
+///
+/// ```
+/// let tokens: Vec<_> = tr::lex::tokenize("é-ü[:alpha:]").map(|t| t.span).collect();
+///
+/// assert_eq!((tokens[1].start, tokens[1].end), (5, 14));
+/// assert_eq!((tokens[1].column, tokens[1].end_column), (3, 12));
+/// ```
+#[derive(Debug,Clone,Copy,PartialEq,Default)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub column: usize,
+    pub end_column: usize,
 }
 
 
-impl Token {
-    fn new<S>(token_type: TokenType, token: S) -> Token where S: Into<String> {
-        Token { token_type: token_type, token: token.into() }
+impl<'a> Token<'a> {
+    fn new<S>(token_type: TokenType, token: S) -> Token<'a> where S: Into<Cow<'a, str>> {
+        Token { token_type, token: token.into(), span: Span::default() }
     }
 }
 
 
 impl<'a> Iterator for Lexer<'a> {
-    type Item = Token;
-
-    fn next(&mut self) -> Option<Token> {
-        if !self.tokens.is_empty() {
-            return Some(self.tokens.remove(0));
-        } else if self.s.is_empty() {
-            return None;
-        } else {
+    type Item = Token<'a>;
+
+    fn next(&mut self) -> Option<Token<'a>> {
+        loop {
+            if let Some(token) = self.tokens.pop_front() {
+                return Some(token);
+            }
+
+            if self.position == self.source.len() {
+                return None;
+            }
+
             self.scan();
-            return self.next();
         }
     }
 }
 
 
 impl<'a> Lexer<'a> {
-    fn emit(&mut self, token: Token) {
-        self.tokens.push(token);
+    /// Read the set as `dialect` does, e.g. its octal escapes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tr::dialect::Dialect;
+    /// use tr::lex::tokenize;
+    ///
+    /// assert_eq!(tokenize(r"\400").next().unwrap().token, " ");
+    /// assert_eq!(tokenize(r"\400").dialect(Dialect::Bsd).next().unwrap().token, "\u{100}");
+    /// ```
+    pub fn dialect(self, dialect: Dialect) -> Lexer<'a> {
+        Lexer { dialect, ..self }
+    }
+
+    /// The warnings about the set read so far: GNU warns of an octal
+    /// escape above `\377`, which it reads as two digits and a literal
+    /// digit.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut tokens = tr::lex::tokenize(r"\400");
+    /// tokens.by_ref().for_each(drop);
+    ///
+    /// assert_eq!(tokens.warnings(), [r"the ambiguous octal escape \400 is being interpreted as the 2-byte sequence \040, 0"]);
+    /// ```
+    pub fn warnings(&self) -> &[String] {
+        &self.warnings
+    }
+
+    /// Warn if the escape sequence read from byte offset `start` to `end`
+    /// is an octal escape cut short by GNU, as `\40` in `\400`.
+    fn check_octal(&mut self, start: usize, end: usize) {
+        let escape = &self.source.as_bytes()[start..end];
+        let is_octal = |b: &u8| (b'0'..=b'7').contains(b);
+
+        if self.dialect == Dialect::Gnu && escape.len() == 3 && escape[0] == b'\\'
+            && escape[1..].iter().all(is_octal) && self.source.as_bytes().get(end).is_some_and(is_octal) {
+            let message = format!("the ambiguous octal escape {} is being interpreted as the 2-byte sequence \\0{}, {}",
+                                  &self.source[start..end + 1], &self.source[start + 1..end], &self.source[end..end + 1]);
+
+            self.warnings.push(message);
+        }
     }
 
+    /// Emit `token`, read from the next `length` bytes of the set.
+    fn emit(&mut self, mut token: Token<'a>, length: usize) {
+        let end = self.position + length;
+        let end_column = self.column + self.source.as_bytes()[self.position..end].graphemes().count();
+
+        token.span = Span { start: self.position, end, column: self.column, end_column };
+        self.position = end;
+        self.column = end_column;
+        self.tokens.push_back(token);
+    }
+
+    /// Emit the literal text from the current position up to byte offset
+    /// `end`, if any.
+    fn emit_literal(&mut self, end: usize) {
+        if end > self.position {
+            let text = &self.source[self.position..end];
+            self.emit(Token::new(TokenType::Literal, text), end - self.position);
+        }
+    }
+
+    /// Test whether a set operator begins at byte offset `i`.
+    fn is_operator(&self, i: usize) -> bool {
+        self.extended && (self.source[i..].starts_with("--") || self.source[i..].starts_with("&&"))
+    }
+
+    /// Scan the last endpoint of a range whose dash is at byte offset `i`,
+    /// as `_range_end`. Neither an operator nor the `]` closing a nested set
+    /// forms a range.
+    fn range_end(&self, i: usize) -> Option<(Token<'a>, usize)> {
+        match self.is_operator(i) || (self.depth > 0 && self.source[i..].starts_with("-]")) {
+            true => None,
+            false => _range_end(&self.source[i..], self.dialect)
+        }
+    }
+
+    /// Scan from the current position up to and including the next token
+    /// that is not literal text, or to the end of the set, emitting the
+    /// tokens found.
     fn scan(&mut self) {
-        use State::{*};
+        let source = self.source;
 
-        let mut consumed = 0;
-        let mut scanned = String::new();
+        for (i, _, c) in source.as_bytes()[self.position..].grapheme_indices() {
+            let i = self.position + i;
 
-        macro_rules! emit_prior {
-             () => {
-                if !scanned.is_empty() {
-                    consumed = scanned.len();
-                    self.emit(Token::new(TokenType::Literal, replace(& mut scanned, String::new())));
-                }
-            }
-        };
-
-        for c in self.s.as_bytes().graphemes() {
-            match self.state {
-                InterpretBackslash => {
-                    match c.chars().next() {
-                        Some('0'..='7') => {
-                            scanned.push_str(c);
-                            consumed += 1;
-                            self.state = InterpretBackslashOctal;
+            match c {
+                "\\" => {
+                    let (token, length) = _is_escape(&source[i..], self.dialect);
+
+                    self.check_octal(i, i + length);
+                    self.emit_literal(i);
+
+                    // an escape may itself begin a range: \u{400}-\u{4ff}
+                    match (&token.token_type, self.range_end(i + length)) {
+                        (TokenType::Literal, Some((last, end)))
+                        if last.token_type == TokenType::Literal => {
+                            self.check_octal(i + length + 1, i + length + end);
+
+                            let range = format!("{}-{}", token.token, last.token);
+                            self.emit(Token::new(TokenType::CharRange, range), length + end);
                         },
-                        _ => {
-                            scanned.push_str(c);
-                            consumed += 1;
-                            self.emit(Token::new(TokenType::Literal, unescape(&scanned)));
-                            break;
-                        }
+                        _ => self.emit(token, length)
                     }
+
+                    return;
                 },
-                InterpretBackslashOctal => {
-                    match c.chars().next() {
-                        Some('0'..='7') if scanned.len() <= 3 => {
-                            scanned.push_str(c);
-                            consumed += 1;
-                        },
-                        _ => {
-                            self.emit(Token::new(TokenType::Literal, octal_to_str(&scanned)));
-                            break;
-                        }
-                    }
+                "-" | "&" if self.is_operator(i) => {
+                    self.emit_literal(i);
+                    self.emit(Token::new(TokenType::SetOperator, &source[i..i + 2]), 2);
+
+                    return;
                 },
-                RangePending => {
-                    // remove the dash
-                    scanned.pop();
+                "-" if i > self.position => {
+                    let (last, length) = match self.range_end(i) {
+                        Some(end) => end,
+                        None => continue
+                    };
 
-                    let first = scanned.pop().unwrap();
+                    self.check_octal(i + 1, i + length);
 
-                    emit_prior!();
+                    // the range begins with the character before the dash
+                    let first = source[..i].chars().next_back().unwrap();
+                    let start = i - first.len_utf8();
 
-                    consumed += 3;
-                    self.emit(Token::new(TokenType::CharRange, format!("{}-{}", first, c)));
-                    break;
-                },
-                _ => {
-                    match c {
-                        "\\" => {
-                            emit_prior!();
-                            scanned.push_str(c);
-                            consumed += 1;
-                            self.state = InterpretBackslash;
-                        },
-                        "-" => {
-                            if !scanned.is_empty() {
-                                self.state = RangePending;
-                            }
+                    self.emit_literal(start);
 
-                            scanned.push_str(c);
+                    match last.token_type {
+                        TokenType::Literal => {
+                            let range: Cow<str> = match last.token {
+                                Cow::Borrowed(_) => source[start..i + length].into(),
+                                Cow::Owned(last) => format!("{}-{}", first, last).into()
+                            };
+
+                            self.emit(Token::new(TokenType::CharRange, range), i + length - start);
+                        },
+                        TokenType::CharClass => {
+                            // a class cannot end a range: a-\p{Nd}
+                            self.emit(Token::new(TokenType::Literal, &source[start..i + 1]), i + 1 - start);
+                            self.emit(last, length - 1);
                         },
-                        "[" => {
-                            let start = scanned.len();
+                        _ => self.emit(last, i + length - start)
+                    }
 
-                            let success = _is_equivalence(&self.s[start..])
-                                .or_else(|| _is_repeat(&self.s[start..]))
-                                .or_else(|| _is_class(&self.s[start..]));
+                    return;
+                },
+                "[" => {
+                    let rest = &source[i..];
 
-                            if let Some((token, length)) = success {
-                                emit_prior!();
+                    let success = _is_equivalence(rest)
+                        .or_else(|| _is_repeat(rest, self.dialect))
+                        .or_else(|| _is_class(rest, self.dialect));
 
-                                self.emit(token);
-                                consumed += length;
+                    if let Some((token, length)) = success {
+                        self.emit_literal(i);
+                        self.emit(token, length);
 
-                                break;
-                            } else {
-                                scanned.push_str(c);
-                            }
-                        },
-                        _ => { scanned.push_str(c); }
+                        return;
+                    } else if self.extended {
+                        self.emit_literal(i);
+                        self.emit(Token::new(TokenType::SetStart, &source[i..i + 1]), 1);
+                        self.depth += 1;
+
+                        return;
                     }
-                }
-            }
-        }
+                },
+                "]" if self.depth > 0 => {
+                    self.emit_literal(i);
+                    self.emit(Token::new(TokenType::SetEnd, &source[i..i + 1]), 1);
+                    self.depth -= 1;
 
-        if self.tokens.is_empty() {
-            consumed = scanned.len();
-            self.emit(Token::new(TokenType::Literal, scanned));
+                    return;
+                },
+                _ => ()
+            }
         }
 
-        self.s = &self.s[consumed..];
-        self.state = ScanLiteral;
+        self.emit_literal(source.len());
     }
 }
 
 
 // TODO: support Unicode repeats
-fn _is_repeat(s: &str) -> Option<(Token, usize)> {
+fn _is_repeat(s: &str, dialect: Dialect) -> Option<(Token<'_>, usize)> {
     use TokenType::{CharRepeat};
 
-    if s.len() < 4 || &s[2..3] != "*" {
+    if s.len() < 4 || s.get(2..3) != Some("*") {
         return None;
     }
 
@@ -183,6 +309,8 @@ fn _is_repeat(s: &str) -> Option<(Token, usize)> {
         match c {
             ']' => { return Some((Token::new(CharRepeat, &s[..i+4]), i + 4)) },
             '0'..='9' => { continue; },
+            // BSD counts may be hex: [c*0x1f]
+            'x' | 'X' | 'a'..='f' | 'A'..='F' if dialect == Dialect::Bsd => { continue; },
             _ => { break; }
         }
     }
@@ -191,10 +319,10 @@ fn _is_repeat(s: &str) -> Option<(Token, usize)> {
 }
 
 
-fn _is_equivalence(s: &str) -> Option<(Token, usize)> {
+fn _is_equivalence(s: &str) -> Option<(Token<'_>, usize)> {
     use TokenType::{Equivalence};
 
-    if s.len() >= 5 && &s[..2] == "[=" && &s[3..5] == "=]" {
+    if s.starts_with("[=") && s.get(3..5) == Some("=]") {
         return Some((Token::new(Equivalence, &s[..5]), 5));
     }
 
@@ -202,23 +330,159 @@ fn _is_equivalence(s: &str) -> Option<(Token, usize)> {
 }
 
 
-fn _is_class(s: &str) -> Option<(Token, usize)> {
-    use TokenType::{CharClass};
+fn _is_class(s: &str, dialect: Dialect) -> Option<(Token<'_>, usize)> {
+    use TokenType::{CharClass, Invalid};
 
-    if s.len() >= 10 && &s[..10] == "[:xdigit:]" {
+    if s.starts_with("[:xdigit:]") {
         return Some((Token::new(CharClass, "[:xdigit:]"), 10));
-    } else if s.len() >= 9 {
-        if &s[..2] == "[:" && &s[7..9] == ":]" {
-            match &s[2..7] {
-                "alnum" | "alpha" | "blank" | "cntrl" | "digit" | "graph" |
-                "lower" | "print" | "punct" | "space" | "upper"
-                => { return Some((Token::new(CharClass, &s[..9]), 9)); },
-                _ => ()
-            }
+    } else if s.starts_with("[:") && s.get(7..9) == Some(":]") {
+        match &s[2..7] {
+            "alnum" | "alpha" | "blank" | "cntrl" | "digit" | "graph" |
+            "lower" | "print" | "punct" | "space" | "upper"
+            => { return Some((Token::new(CharClass, &s[..9]), 9)); },
+            _ => ()
         }
     }
 
-    None
+    // otherwise a Unicode property class: [:script=Greek:], [:gc=Lu:],
+    // [:emoji:], or, unless POSIX, which has none, an unknown class. The
+    // name ends at the first bracket, which must close it.
+    let rest = s.strip_prefix("[:")?;
+    let end = rest.find(['[', ']'])?;
+    let name = rest[..end].strip_suffix(':').filter(|_| rest[end..].starts_with(']'))?;
+
+    if name.is_empty() {
+        return None;
+    }
+
+    let length = name.len() + 4;
+
+    match dialect != Dialect::Posix && Property::parse(name).is_some() {
+        true => Some((Token::new(CharClass, &s[..length]), length)),
+        false => Some((Token::new(Invalid, &s[..length]), length))
+    }
+}
+
+
+/// Scan the last endpoint of a range, given `s` starting at the dash.
+///
+/// The endpoint is a single grapheme or an escape sequence. Returns the
+/// endpoint as a Literal token, or an Invalid one for a malformed escape,
+/// together with the length of the dash and endpoint. Returns None if `s`
+/// is not a dash followed by an endpoint.
+fn _range_end(s: &str, dialect: Dialect) -> Option<(Token<'_>, usize)> {
+    let mut graphemes = s.as_bytes().graphemes();
+
+    match (graphemes.next(), graphemes.next()) {
+        (Some("-"), Some("\\")) => {
+            let (token, length) = _is_escape(&s[1..], dialect);
+
+            Some((token, length + 1))
+        },
+        (Some("-"), Some(c)) => Some((Token::new(TokenType::Literal, c), c.len() + 1)),
+        _ => None
+    }
+}
+
+
+/// Scan the escape sequence at the start of `s`, which begins with a
+/// backslash.
+///
+/// Besides the escapes understood by `unescape`, recognize
+///
+///    \NNN       the character with octal value NNN (1 to 3 digits)
+///    \xHH       the character U+00HH (1 or 2 hex digits)
+///    \uHHHH     the character with hex code point HHHH (exactly 4 digits)
+///    \u{H...}   the character with hex code point H... (1 to 6 digits)
+///    \N{NAME}   the character named NAME, e.g. \N{NO-BREAK SPACE}
+///    \p{NAME}   the characters with Unicode property NAME, e.g. \p{Nd}
+///
+/// An `x` or `u` without the digits it calls for is taken literally, as
+/// is an `N` or `p` without braces. A code point that is not a Unicode
+/// scalar value, an unknown name or an unknown property yields an Invalid
+/// token. A property escape yields a CharClass token.
+///
+/// Octal escapes above \377 are read as GNU does, as two digits followed
+/// by a literal digit, which the lexer warns of, except in BSD, which
+/// reads all three. POSIX leaves them undefined, and has none of the
+/// other escapes above, so in that dialect they yield an Invalid token.
+///
+/// Returns the token and the number of bytes of `s` it covers.
+fn _is_escape(s: &str, dialect: Dialect) -> (Token<'_>, usize) {
+    use TokenType::{CharClass, Invalid, Literal};
+
+    let rest = &s[1..];
+    let hex_digits = |s: &str, max| s.chars().take(max).take_while(char::is_ascii_hexdigit).count();
+    let code_point = |digits: &str, length| match u32::from_str_radix(digits, 16).ok()
+                                                   .and_then(std::char::from_u32) {
+        Some(c) => (Token::new(Literal, c.to_string()), length),
+        None => (Token::new(Invalid, &s[..length]), length)
+    };
+    let close = |s: &str| s.char_indices().take(MAX_NAME).find(|&(_, c)| c == '}').map(|(i, _)| i);
+
+    let (token, length) = match rest.chars().next() {
+        None => (Token::new(Literal, "\\"), 1),
+        Some('0'..='7') => {
+            let digits = rest.chars().take(3).take_while(|c| ('0'..='7').contains(c)).count();
+            let value = octal_value(&rest[..digits]);
+
+            match (dialect, std::char::from_u32(value)) {
+                _ if value <= 0o377 => (Token::new(Literal, char::from(value as u8).to_string()), 1 + digits),
+                // GNU reads \400 as \40 followed by 0
+                (Dialect::Gnu, _) => (Token::new(Literal, char::from(octal_value(&rest[..2]) as u8).to_string()), 3),
+                (Dialect::Bsd, Some(c)) => (Token::new(Literal, c.to_string()), 1 + digits),
+                _ => (Token::new(Invalid, &s[..1 + digits]), 1 + digits)
+            }
+        },
+        Some('x') if hex_digits(&rest[1..], 2) > 0 => {
+            let length = 2 + hex_digits(&rest[1..], 2);
+
+            code_point(&s[2..length], length)
+        },
+        Some('u') if rest[1..].starts_with('{') => {
+            let digits = hex_digits(&rest[2..], 6);
+
+            match rest[2 + digits..].starts_with('}') && digits > 0 {
+                true => code_point(&s[3..3 + digits], 4 + digits),
+                false => (Token::new(Literal, "u"), 2)
+            }
+        },
+        Some('u') if hex_digits(&rest[1..], 4) == 4 => code_point(&s[2..6], 6),
+        Some('N') if rest[1..].starts_with('{') => match close(rest) {
+            Some(end) => {
+                let length = end + 2;
+
+                match unicode_names2::character(&rest[2..end]) {
+                    Some(c) => (Token::new(Literal, c.to_string()), length),
+                    None => (Token::new(Invalid, &s[..length]), length)
+                }
+            },
+            None => (Token::new(Literal, "N"), 2)
+        },
+        Some('p') if rest[1..].starts_with('{') => match close(rest) {
+            Some(end) => {
+                let length = end + 2;
+
+                match Property::parse_short(&rest[2..end]) {
+                    Some(_) => (Token::new(CharClass, &s[..length]), length),
+                    None => (Token::new(Invalid, &s[..length]), length)
+                }
+            },
+            None => (Token::new(Literal, "p"), 2)
+        },
+        Some(c) => {
+            let length = 1 + c.len_utf8();
+
+            (Token::new(Literal, unescape(&s[..length])), length)
+        }
+    };
+
+    match rest.chars().next() {
+        Some('x') | Some('u') | Some('N') | Some('p') if dialect == Dialect::Posix && length > 2 => {
+            (Token::new(Invalid, &s[..length]), length)
+        },
+        _ => (token, length)
+    }
 }
 
 
@@ -258,18 +522,36 @@ pub fn unescape(s: &str) -> &str {
 }
 
 
-fn octal_to_str(s: &str) -> char {
-    let mut index = 0;
+/// The value of `digits`, at most three octal digits.
+fn octal_value(digits: &str) -> u32 {
+    digits.bytes().fold(0, |value, d| value * 8 + u32::from(d - b'0'))
+}
 
-    if &s[..1] == r"\" {
-        index = 1;
-    }
 
-    // FIXME!
-    std::char::from_u32(u32::from_str_radix(&s[index..], 8).unwrap()).unwrap()
+pub fn tokenize(s: &str) -> Lexer<'_> {
+    Lexer { source: s, position: 0, column: 0, tokens: VecDeque::new(), extended: false,
+            dialect: Dialect::Gnu, depth: 0, warnings: vec![] }
 }
 
 
-pub fn tokenize(s: &str) -> Lexer {
-    Lexer { s: s, tokens: vec![], state: State::ScanLiteral }
+/// Tokenize `s` in extended syntax, which adds set operators and nested
+/// sets to the GNU syntax:
+///
+///    [...]      a nested set
+///    A--B       the members of A that are not in B
+///    A&&B       the members of A that are also in B
+///
+/// # Examples
+///
+/// ```
+/// use tr::lex::TokenType::{*};
+///
+/// let tokens: Vec<_> = tr::lex::tokenize_extended("[:alpha:]--[aeiou]")
+///     .map(|t| t.token_type)
+///     .collect();
+///
+/// assert_eq!(tokens, vec![CharClass, SetOperator, SetStart, Literal, SetEnd]);
+/// ```
+pub fn tokenize_extended(s: &str) -> Lexer<'_> {
+    Lexer { extended: true, ..tokenize(s) }
 }
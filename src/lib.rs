@@ -1,4 +1,18 @@
 pub mod arg_parser;
+pub mod bytes;
+pub mod canonical;
 pub mod command;
+pub mod complement;
+pub mod dialect;
+pub mod diff;
+pub mod explain;
+pub mod fields;
+pub mod follow;
+pub mod json;
 pub mod lex;
 pub mod parser;
+pub mod preset;
+pub mod property;
+pub mod record;
+pub mod select;
+pub mod stats;
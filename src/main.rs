@@ -1,3 +1,5 @@
+use std::io::{BufWriter, Write};
+
 use tr::command::tr;
 
 
@@ -6,10 +8,14 @@ fn main() {
     let stdout = std::io::stdout();
 
     let stdin = stdin.lock();
-    let mut stdout = stdout.lock();
+    let mut stdout = BufWriter::new(stdout.lock());
+
+    let result = tr(std::env::args_os(), stdin, &mut stdout, &mut std::io::stderr())
+        .and_then(|_| stdout.flush().map_err(|e| e.to_string()));
 
-    let exit_code = match tr(std::env::args(), stdin, &mut stdout) {
+    let exit_code = match result {
         Err(message) => {
+            let _ = stdout.flush();
             eprintln!("tr: {}", message);
             1
         },
@@ -1,10 +1,13 @@
 use std::borrow::Cow;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use bstr::{ByteSlice};
 
-use crate::lex::tokenize;
+use crate::arg_parser::Config;
+use crate::dialect::Dialect;
+use crate::lex::{tokenize, tokenize_extended, Lexer, Span, Token};
 use crate::lex::TokenType::{*};
+use crate::property::Property;
 
 
 /// Create a mapping from each char in set1 to the corresponding char
@@ -13,12 +16,74 @@ use crate::lex::TokenType::{*};
 /// # Examples
 ///
 /// ```
-/// let map = tr::parser::map_charsets("abcde", "zyxwv");
+/// let map = tr::parser::map_charsets("abcde", "zyxwv").unwrap();
 ///
 /// assert_eq!(&"x", map.get("c").unwrap());
 /// ```
-pub fn map_charsets<'a>(set1: &'a str, set2: &'a str) -> HashMap<String, String> {
-    parse(set1).as_bytes().graphemes().zip(parse(set2).as_bytes().graphemes())
+pub fn map_charsets<'a>(set1: &'a str, set2: &'a str) -> Result<HashMap<String, String>, String> {
+    Syntax::default().map(set1, set2, false)
+}
+
+
+/// Create a mapping from each grapheme in set1 to the corresponding
+/// target, which, unlike a grapheme of set2, may be several graphemes or
+/// none. If there are fewer targets than graphemes, the last target is
+/// repeated to make up the difference.
+///
+/// # Examples
+///
+/// ```
+/// let map = tr::parser::map_targets("ЖЩa-c", &["Zh", "Shch", "", "C"]).unwrap();
+///
+/// assert_eq!("Shch", map["Щ"]);
+/// assert_eq!("", map["a"]);
+/// assert_eq!("C", map["c"]);
+/// ```
+pub fn map_targets<S: AsRef<str>>(set1: &str, targets: &[S]) -> Result<HashMap<String, String>, String> {
+    Ok(map_expanded_targets(&Syntax::default().expand_set1(set1)?, targets))
+}
+
+
+/// Create a mapping from each grapheme in set1, taken as already
+/// expanded, to the corresponding target, as `map_targets`.
+pub(crate) fn map_expanded_targets<S: AsRef<str>>(set1: &str, targets: &[S]) -> HashMap<String, String> {
+    let last = targets.last().into_iter().cycle();
+
+    set1.as_bytes().graphemes().zip(targets.iter().chain(last))
+        .map(|(g, target)| (g.to_string(), target.as_ref().to_owned()))
+        .collect()
+}
+
+
+/// Create a mapping from each grapheme in set1 to the corresponding
+/// grapheme in set2.
+///
+/// Both sets are taken as already expanded. If set2 is shorter than set1,
+/// its last grapheme is repeated to make up the difference, unless
+/// `truncate` is `true`, in which case the extra graphemes of set1 are
+/// left unmapped.
+///
+/// # Examples
+///
+/// ```
+/// let map = tr::parser::map_graphemes("a-z", "*", false);
+///
+/// assert_eq!(&"*", map.get("-").unwrap());
+/// assert_eq!(&"*", map.get("z").unwrap());
+///
+/// let map = tr::parser::map_graphemes("a-z", "*", true);
+///
+/// assert_eq!(None, map.get("z"));
+/// ```
+pub fn map_graphemes(set1: &str, set2: &str, truncate: bool) -> HashMap<String, String> {
+    let last = match truncate {
+        true => None,
+        false => set2.as_bytes().graphemes().next_back()
+    };
+
+    let set2 = set2.as_bytes().graphemes().chain(last.into_iter().cycle());
+
+    set1.as_bytes().graphemes().zip(set2)
         .map(|(c1, c2)| (c1.to_string(), c2.to_string()))
         .collect()
 }
@@ -42,7 +107,7 @@ pub fn rpad_last<'a>(s: &'a str, n: usize) -> Cow<'a, str> {
     if s.len() < n {
         let mut buf = String::with_capacity(n);
         let n = n - s.len();
-        let c = s.chars().rev().nth(0).expect("empty source string");
+        let c = s.chars().next_back().expect("empty source string");
 
         buf.push_str(s);
         buf.push_str(&c.to_string().repeat(n));
@@ -61,7 +126,7 @@ fn expand_range(s: &str) -> String {
 }
 
 
-fn expand_class(s: &str) -> String {
+pub(crate) fn expand_class(s: &str) -> String {
     match s {
         "[:alnum:]" => "0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz",
         "[:alpha:]" => "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz",
@@ -80,49 +145,584 @@ fn expand_class(s: &str) -> String {
         "[:space:]" => "\t\n\u{b}\u{c}\r ",
         "[:upper:]" => "ABCDEFGHIJKLMNOPQRSTUVWXYZ",
         "[:xdigit:]" => "0123456789ABCDEFabcdef",
-        _ => panic!("tried to expand non class {:?}", s)
+        _ => {
+            let property = class_property(s).expect("tried to expand non class");
+
+            return property.chars().collect();
+        }
     }.into()
 }
 
 
-fn expand_repeat(s: &str) -> String {
-    let b = s.as_bytes();
-    let b = &b[1..b.len()-1];
+/// Interpret a Unicode property class, [:NAME:] or \p{NAME}.
+fn class_property(s: &str) -> Option<Property> {
+    if let Some(name) = s.strip_prefix("[:").and_then(|s| s.strip_suffix(":]")) {
+        Property::parse(name)
+    } else if let Some(name) = s.strip_prefix("\\p{").and_then(|s| s.strip_suffix('}')) {
+        Property::parse_short(name)
+    } else {
+        None
+    }
+}
 
-    let v: Vec<_> = b.splitn_str(2, "*").collect();
-    println!("{:?}", v);
 
+/// An error in a set, with the part of the set it concerns, if known.
+struct SetError {
+    message: String,
+    span: Option<Span>,
+}
 
-    let c = std::str::from_utf8(v[0]).unwrap();
-    let n = std::str::from_utf8(v[1]).unwrap().parse::<usize>().unwrap();
 
-    c.repeat(n)
+impl SetError {
+    fn new(message: String, span: Span) -> SetError {
+        SetError { message, span: Some(span) }
+    }
+
+    /// Show the error in the set `s`, called `name` if given, with a caret
+    /// under the part it concerns:
+    ///
+    ///    invalid character class ‘[:alfa:]’
+    ///      SET1: a-z[:alfa:]
+    ///               ^^^^^^^^
+    fn render(self, s: &str, name: Option<&str>) -> String {
+        let label = name.map_or(String::new(), |name| format!("{}: ", name));
+
+        match self.span {
+            Some(span) => {
+                let indent = label.chars().count() + span.column;
+                let width = (span.end_column - span.column).max(1);
+
+                format!("{}\n  {}{}\n  {}{}", self.message, label, s, " ".repeat(indent), "^".repeat(width))
+            },
+            None => format!("{}\n  {}{}", self.message, label, s)
+        }
+    }
 }
 
 
-pub fn parse<'a>(s: &'a str) -> Cow<'a, str> {
-    if s.is_empty() {
-        return s.into();
+/// Split the repeat `token`, `[c*n]`, into `c` and its count as read in
+/// `dialect`, which is None for a repeat that fills: `[c*]` or `[c*0]`.
+fn repeat<'a>(token: &'a Token, dialect: Dialect) -> Result<(&'a str, Option<usize>), SetError> {
+    let s = &token.token;
+    let (c, count) = s[1..s.len() - 1].split_once('*').expect("tried to expand non repeat");
+
+    match count {
+        "" => Ok((c, None)),
+        _ => match dialect.repeat_count(count) {
+            Some(0) => Ok((c, None)),
+            Some(n) => Ok((c, Some(n))),
+            None => Err(SetError::new(format!("invalid repeat count ‘{}’ in [c*n] construct", count),
+                                      token.span))
+        }
     }
+}
+
+
+fn expand_repeat(token: &Token, dialect: Dialect, fill: usize) -> Result<String, SetError> {
+    let (c, n) = repeat(token, dialect)?;
+
+    Ok(c.repeat(n.unwrap_or(fill)))
+}
 
-    let mut tokens = tokenize(s);
-    let token = tokens.next().unwrap();
 
-    if token.token_type == Literal && token.token == s {
-        return s.into();
+/// Expand a set into the graphemes it denotes.
+///
+/// Ranges, classes, repeats and escape sequences are replaced by the
+/// characters they stand for; everything else stands for itself. Unicode
+/// property classes expand to every character with the property, in code
+/// point order.
+///
+/// Returns Err("message") if the set contains malformed syntax, such as
+/// an escape naming an unknown character. The message shows the set with
+/// a caret under the offending part.
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!("abc\u{a0}", tr::parser::parse(r"a-c\N{NO-BREAK SPACE}").unwrap());
+/// assert!(tr::parser::parse(r"\N{NO SUCH CHARACTER}").is_err());
+///
+/// assert_eq!(tr::parser::parse("a-z[:alfa:]").unwrap_err(),
+///            "invalid character class ‘[:alfa:]’\n  a-z[:alfa:]\n     ^^^^^^^^");
+/// ```
+pub fn parse<'a>(s: &'a str) -> Result<Cow<'a, str>, String> {
+    Syntax::default().expand(s)
+}
+
+
+/// Expand `token` into `output`, reading repeat counts as `dialect` does
+/// and filling `fill` copies for a repeat without a count.
+fn expand_token(token: &Token, dialect: Dialect, fill: usize, output: &mut String) -> Result<(), SetError> {
+    match token.token_type {
+        Literal => output.push_str(&token.token),
+        CharRange => match expand_range(&token.token) {
+            range if range.is_empty() => {
+                return Err(SetError::new(format!("range-endpoints of ‘{}’ are in reverse collating sequence order",
+                                                 token.token), token.span));
+            },
+            range => output.push_str(&range)
+        },
+        CharClass => output.push_str(&expand_class(&token.token)),
+        CharRepeat => output.push_str(&expand_repeat(token, dialect, fill)?),
+        Invalid => {
+            let kind = match token.token.starts_with("[:") {
+                true => "character class",
+                false => "escape sequence"
+            };
+
+            return Err(SetError::new(format!("invalid {} ‘{}’", kind, token.token), token.span));
+        },
+        _ => ()
+    }
+
+    Ok(())
+}
+
+
+/// The graphemes a set matches, for deleting and squeezing.
+///
+/// Unicode property classes are kept as properties rather than expanded,
+/// so that a grapheme of several characters, such as an emoji sequence,
+/// is tested as a whole.
+#[derive(Debug,Default)]
+pub struct CharSet {
+    graphemes: HashSet<String>,
+    members: Vec<Member>,
+}
+
+
+#[derive(Debug)]
+enum Member {
+    Property(Property),
+    Difference(CharSet, CharSet),
+    Intersection(CharSet, CharSet),
+}
+
+
+impl CharSet {
+    /// Test whether the set matches `grapheme`.
+    pub fn contains(&self, grapheme: &str) -> bool {
+        self.graphemes.contains(grapheme)
+            || self.members.iter().any(|m| m.contains(grapheme))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.graphemes.is_empty() && self.members.is_empty()
+    }
+}
+
+
+impl Member {
+    fn contains(&self, grapheme: &str) -> bool {
+        match self {
+            Member::Property(property) => property.contains(grapheme),
+            Member::Difference(a, b) => a.contains(grapheme) && !b.contains(grapheme),
+            Member::Intersection(a, b) => a.contains(grapheme) && b.contains(grapheme),
+        }
+    }
+}
+
+
+impl From<&str> for CharSet {
+    /// Create the set of the graphemes in the already expanded `s`.
+    fn from(s: &str) -> CharSet {
+        let graphemes = s.as_bytes().graphemes().map(String::from).collect();
+
+        CharSet { graphemes, members: vec![] }
+    }
+}
+
+
+/// A set in extended syntax, as a tree of set operations.
+enum Expr<'a> {
+    Token(Token<'a>),
+    Union(Vec<Expr<'a>>),
+    Difference(Box<Expr<'a>>, Box<Expr<'a>>),
+    Intersection(Box<Expr<'a>>, Box<Expr<'a>>),
+}
+
+
+impl Expr<'_> {
+    /// Expand into the graphemes the expression denotes, in order.
+    ///
+    /// The result of an operation keeps the order of its left operand.
+    fn expand(&self, dialect: Dialect, fill: usize, output: &mut String) -> Result<(), SetError> {
+        let mut filter = |a: &Expr, b: &Expr, keep: bool| -> Result<(), SetError> {
+            let (mut left, right) = (String::new(), b.to_set(dialect)?);
+
+            a.expand(dialect, fill, &mut left)?;
+            output.extend(left.as_bytes().graphemes().filter(|g| right.contains(g) == keep));
+            Ok(())
+        };
+
+        match self {
+            Expr::Token(token) => expand_token(token, dialect, fill, output),
+            Expr::Union(exprs) => exprs.iter().try_for_each(|e| e.expand(dialect, fill, output)),
+            Expr::Difference(a, b) => filter(a, b, false),
+            Expr::Intersection(a, b) => filter(a, b, true),
+        }
     }
 
-    let mut output = String::with_capacity(s.len());
+    /// Add the graphemes the expression matches to `set`.
+    fn add_to(&self, dialect: Dialect, set: &mut CharSet) -> Result<(), SetError> {
+        match self {
+            Expr::Token(token) => match (&token.token_type, class_property(&token.token)) {
+                (CharClass, Some(property)) => set.members.push(Member::Property(property)),
+                _ => {
+                    let mut expanded = String::new();
 
-    for token in std::iter::once(token).chain(tokens) {
-        match token.token_type {
-            Literal => output.push_str(&token.token),
-            CharRange => output.push_str(&expand_range(&token.token)),
-            CharClass => output.push_str(&expand_class(&token.token)),
-            CharRepeat => output.push_str(&expand_repeat(&token.token)),
-            _ => ()
+                    // a repeat that fills matches its grapheme all the same
+                    expand_token(token, dialect, 1, &mut expanded)?;
+                    set.graphemes.extend(expanded.as_bytes().graphemes().map(String::from));
+                }
+            },
+            Expr::Union(exprs) => {
+                for expr in exprs {
+                    expr.add_to(dialect, set)?;
+                }
+            },
+            Expr::Difference(a, b) => {
+                set.members.push(Member::Difference(a.to_set(dialect)?, b.to_set(dialect)?))
+            },
+            Expr::Intersection(a, b) => {
+                set.members.push(Member::Intersection(a.to_set(dialect)?, b.to_set(dialect)?))
+            },
         }
+
+        Ok(())
     }
 
-    output.into()
+    fn to_set(&self, dialect: Dialect) -> Result<CharSet, SetError> {
+        let mut set = CharSet::default();
+
+        self.add_to(dialect, &mut set)?;
+        Ok(set)
+    }
+}
+
+
+/// Build the expression tree of the tokens up to the end of the set, or
+/// of the nested set opened at `opening`, if given.
+///
+/// Operators bind the operands on either side of them, left to right;
+/// everything else is a union.
+fn parse_expr<'a>(tokens: &mut Lexer<'a>, opening: Option<Span>) -> Result<Expr<'a>, SetError> {
+    let mut exprs = vec![];
+
+    let operand = |tokens: &mut Lexer<'a>, token: Option<Token<'a>>| match token {
+        Some(Token { token_type: SetStart, span, .. }) => parse_expr(tokens, Some(span)).map(Some),
+        Some(Token { token_type: SetOperator, .. }) | Some(Token { token_type: SetEnd, .. }) | None => {
+            Ok(None)
+        },
+        Some(token) => Ok(Some(Expr::Token(token)))
+    };
+
+    while let Some(token) = tokens.next() {
+        let expr = match token.token_type {
+            SetEnd => return Ok(Expr::Union(exprs)),
+            SetOperator => {
+                let missing = |side| SetError::new(format!("missing operand {} ‘{}’", side, token.token),
+                                                   token.span);
+                let left = exprs.pop().ok_or_else(|| missing("before"))?;
+                let next = tokens.next();
+                let right = operand(tokens, next)?.ok_or_else(|| missing("after"))?;
+
+                match &*token.token {
+                    "--" => Expr::Difference(Box::new(left), Box::new(right)),
+                    _ => Expr::Intersection(Box::new(left), Box::new(right))
+                }
+            },
+            _ => operand(tokens, Some(token))?.expect("a token other than an operator or ‘]’")
+        };
+
+        exprs.push(expr);
+    }
+
+    match opening {
+        Some(span) => Err(SetError::new("missing ‘]’ in set".to_owned(), span)),
+        None => Ok(Expr::Union(exprs))
+    }
+}
+
+
+/// Expand a set in extended syntax into the graphemes it denotes.
+///
+/// Besides the GNU syntax understood by `parse`, a set may contain nested
+/// sets in brackets and the operators `--` (difference) and `&&`
+/// (intersection), which combine the operands on either side of them; see
+/// `crate::lex::tokenize_extended`. The result of an operation lists the
+/// members of its left operand in their original order.
+///
+/// # Examples
+///
+/// ```
+/// use tr::parser::parse_extended;
+///
+/// assert_eq!("bcdfg", parse_extended("a-g--[aeiou]").unwrap());
+/// assert_eq!("-_", parse_extended("[:punct:]&&[_-]").unwrap());
+/// ```
+pub fn parse_extended(s: &str) -> Result<String, String> {
+    Syntax { extended: true, ..Syntax::default() }.expand(s).map(Cow::into_owned)
+}
+
+
+/// Parse a set into the graphemes it matches.
+///
+/// Like `parse`, but Unicode property classes are not expanded.
+///
+/// # Examples
+///
+/// ```
+/// let set = tr::parser::parse_set("[:emoji:]").unwrap();
+///
+/// assert!(set.contains("👍🏽"));
+/// assert!(!set.contains("1"));
+/// ```
+pub fn parse_set(s: &str) -> Result<CharSet, String> {
+    Syntax::default().members(s)
+}
+
+
+/// Parse a set in extended syntax into the graphemes it matches.
+///
+/// # Examples
+///
+/// ```
+/// let set = tr::parser::parse_set_extended("[:emoji:]--[❤️]").unwrap();
+///
+/// assert!(set.contains("👍🏽"));
+/// assert!(!set.contains("❤️"));
+/// ```
+pub fn parse_set_extended(s: &str) -> Result<CharSet, String> {
+    Syntax { extended: true, ..Syntax::default() }.members(s)
+}
+
+
+/// How to read a set: in extended syntax or not, and as which dialect of
+/// tr; see `crate::dialect::Dialect`.
+#[derive(Debug,Clone,Copy,Default)]
+pub struct Syntax {
+    pub extended: bool,
+    pub dialect: Dialect,
+}
+
+
+impl Syntax {
+    /// The syntax `config` selects.
+    pub fn of(config: &Config) -> Syntax {
+        Syntax { extended: config.extended, dialect: config.dialect }
+    }
+
+    fn tokenize(self, s: &str) -> Lexer<'_> {
+        let tokens = match self.extended {
+            true => tokenize_extended(s),
+            false => tokenize(s)
+        };
+
+        tokens.dialect(self.dialect)
+    }
+
+    /// Expand a set into the graphemes it denotes, as `parse` or
+    /// `parse_extended`. A repeat without a count stands for nothing.
+    pub fn expand<'a>(self, s: &'a str) -> Result<Cow<'a, str>, String> {
+        self.expansion(s).map_err(|e| e.render(s, None))
+    }
+
+    /// The warnings about `s` its lexer gives, such as for an ambiguous
+    /// octal escape.
+    pub fn warnings(self, s: &str) -> Vec<String> {
+        let mut tokens = self.tokenize(s);
+
+        tokens.by_ref().for_each(drop);
+        tokens.warnings().to_vec()
+    }
+
+    fn expansion<'a>(self, s: &'a str) -> Result<Cow<'a, str>, SetError> {
+        if s.is_empty() {
+            return Ok(s.into());
+        }
+
+        if !self.extended {
+            let mut tokens = self.tokenize(s);
+
+            if let Some(Token { token_type: Literal, token, .. }) = tokens.next() {
+                if token == s && tokens.next().is_none() {
+                    return self.characters(s, s.into());
+                }
+            }
+        }
+
+        self.expand_filled(s, 0).and_then(|expanded| self.characters(s, expanded.into()))
+    }
+
+    fn expand_filled(self, s: &str, fill: usize) -> Result<String, SetError> {
+        let mut output = String::with_capacity(s.len());
+
+        parse_expr(&mut self.tokenize(s), None)?.expand(self.dialect, fill, &mut output)?;
+        Ok(output)
+    }
+
+    /// Expand SET1, in which a repeat may not appear.
+    pub fn expand_set1<'a>(self, s: &'a str) -> Result<Cow<'a, str>, String> {
+        self.reject_repeats(s)
+            .and_then(|_| self.expansion(s))
+            .map_err(|e| e.render(s, Some("SET1")))
+    }
+
+    /// Expand SET2 given the `length` of SET1, in graphemes: a repeat
+    /// without a count, `[c*]` or `[c*0]`, fills SET2 to that length.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let syntax = tr::parser::Syntax::default();
+    ///
+    /// assert_eq!("a......h", syntax.expand_set2("a[.*]h", 8).unwrap());
+    /// assert_eq!("...a", syntax.expand_set2("[.*3]a", 10).unwrap());
+    /// ```
+    pub fn expand_set2(self, s: &str, length: usize) -> Result<String, String> {
+        self.set2_expansion(s, length).map_err(|e| e.render(s, Some("SET2")))
+    }
+
+    fn set2_expansion(self, s: &str, length: usize) -> Result<String, SetError> {
+        let mut fills = vec![];
+
+        for token in self.tokenize(s).filter(|t| t.token_type == CharRepeat) {
+            if repeat(&token, self.dialect)?.1.is_none() {
+                fills.push(token.span);
+            }
+        }
+
+        let expanded = self.expansion(s)?.into_owned();
+
+        match fills.as_slice() {
+            [] => Ok(expanded),
+            [_] => {
+                let fill = length.saturating_sub(expanded.as_bytes().graphemes().count());
+                self.expand_filled(s, fill)
+            },
+            [_, second, ..] => {
+                Err(SetError::new("only one [c*] repeat construct may appear in SET2".to_owned(), *second))
+            }
+        }
+    }
+
+    /// Split SET2 around a repeat that fills, for when the length of SET1
+    /// is not known: into the graphemes before the repeat, the grapheme it
+    /// repeats, if any, and the graphemes after it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let syntax = tr::parser::Syntax::default();
+    ///
+    /// assert_eq!(("ab".to_owned(), Some(".".to_owned()), "z".to_owned()),
+    ///            syntax.split_set2("ab[.*]z").unwrap());
+    /// assert_eq!(("xyz".to_owned(), None, "".to_owned()), syntax.split_set2("x-z").unwrap());
+    /// ```
+    pub fn split_set2(self, s: &str) -> Result<(String, Option<String>, String), String> {
+        self.split_set(s, "SET2")
+    }
+
+    /// Split a set as `split_set2` does, naming it `name` in errors.
+    pub fn split_set(self, s: &str, name: &str) -> Result<(String, Option<String>, String), String> {
+        let without = self.set2_expansion(s, 0).map_err(|e| e.render(s, Some(name)))?;
+        let length = without.as_bytes().graphemes().count();
+        let with = self.set2_expansion(s, length + 1).map_err(|e| e.render(s, Some(name)))?;
+
+        if with.len() == without.len() {
+            return Ok((without, None, String::new()));
+        }
+
+        // the fill is the first grapheme that differs
+        let (start, end) = without.as_bytes().grapheme_indices()
+            .zip(with.as_bytes().grapheme_indices())
+            .find(|((_, _, a), (_, _, b))| a != b)
+            .map_or((without.len(), with.len()), |((start, ..), (_, end, _))| (start, end));
+
+        Ok((without[..start].to_owned(), Some(with[start..end].to_owned()), without[start..].to_owned()))
+    }
+
+    /// Parse a set into the graphemes it matches, as `parse_set` or
+    /// `parse_set_extended`.
+    pub fn members(self, s: &str) -> Result<CharSet, String> {
+        self.membership(s).map_err(|e| e.render(s, None))
+    }
+
+    fn membership(self, s: &str) -> Result<CharSet, SetError> {
+        let set = parse_expr(&mut self.tokenize(s), None)?.to_set(self.dialect)?;
+
+        match set.graphemes.iter().find(|g| !self.is_character(g)) {
+            Some(g) => Err(not_a_character(s, g)),
+            None => Ok(set)
+        }
+    }
+
+    /// Parse SET1 into the graphemes it matches, rejecting repeats.
+    pub fn members_set1(self, s: &str) -> Result<CharSet, String> {
+        self.reject_repeats(s)
+            .and_then(|_| self.membership(s))
+            .map_err(|e| e.render(s, Some("SET1")))
+    }
+
+    /// Parse SET2 into the graphemes it matches.
+    pub fn members_set2(self, s: &str) -> Result<CharSet, String> {
+        self.membership(s).map_err(|e| e.render(s, Some("SET2")))
+    }
+
+    /// Create a mapping from each grapheme in SET1 to the corresponding
+    /// grapheme in SET2, as `map_graphemes`.
+    ///
+    /// In POSIX, SET2 may not be shorter than SET1 unless truncating.
+    pub fn map(self, set1: &str, set2: &str, truncate: bool) -> Result<HashMap<String, String>, String> {
+        let set1 = self.expand_set1(set1)?;
+        let length = set1.as_bytes().graphemes().count();
+        let set2 = self.expand_set2(set2, length)?;
+
+        if self.dialect == Dialect::Posix && !truncate && set2.as_bytes().graphemes().count() < length {
+            return Err("SET2 is shorter than SET1, which POSIX leaves undefined".to_owned());
+        }
+
+        Ok(map_graphemes(&set1, &set2, truncate))
+    }
+
+    fn reject_repeats(self, s: &str) -> Result<(), SetError> {
+        match self.tokenize(s).find(|t| t.token_type == CharRepeat) {
+            Some(token) => {
+                Err(SetError::new("the [c*] repeat construct may not appear in SET1".to_owned(), token.span))
+            },
+            None => Ok(())
+        }
+    }
+
+    /// Whether `g` may be a member of a set: in POSIX, which knows nothing
+    /// of graphemes, only a single character may.
+    fn is_character(self, g: &str) -> bool {
+        self.dialect != Dialect::Posix || g.chars().nth(1).is_none()
+    }
+
+    /// Check the expansion `expanded` of the set `s` for graphemes that may
+    /// not be members.
+    fn characters<'a>(self, s: &str, expanded: Cow<'a, str>) -> Result<Cow<'a, str>, SetError> {
+        match expanded.as_bytes().graphemes().find(|g| !self.is_character(g)) {
+            Some(g) => Err(not_a_character(s, g)),
+            None => Ok(expanded)
+        }
+    }
+}
+
+
+/// The error for a grapheme of several characters in a POSIX set `s`,
+/// pointing at it if it is written out in the set.
+fn not_a_character(s: &str, g: &str) -> SetError {
+    let message = format!("‘{}’ is a grapheme of several characters, which POSIX tr does not support", g);
+
+    let span = s.find(g).map(|start| {
+        let column = s.as_bytes()[..start].graphemes().count();
+        let end = start + g.len();
+
+        Span { start, end, column, end_column: column + s.as_bytes()[start..end].graphemes().count() }
+    });
+
+    SetError { message, span }
 }
@@ -0,0 +1,248 @@
+use std::collections::HashMap;
+
+use unicode_properties::{GeneralCategoryGroup, UnicodeGeneralCategory};
+
+use bstr::ByteSlice;
+
+use crate::parser::map_expanded_targets;
+
+
+/// A named pair of translation sets.
+///
+/// Unlike SET1 and SET2 given on the command line, preset sets are stored
+/// already expanded: each grapheme stands for itself, with no ranges,
+/// classes or escapes to interpret. Where set2 is shorter than set1 it is
+/// padded with its last grapheme, as for user sets.
+///
+/// `pairs` holds translations that sets cannot express, where a grapheme
+/// maps to several output graphemes (Ж → Zh) or to none at all.
+#[derive(Debug)]
+pub struct Preset {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub set1: &'static str,
+    pub set2: &'static str,
+    pub pairs: &'static [(&'static str, &'static str)],
+    /// Whether combining marks after a letter are dropped before the
+    /// letter is looked up, so that decomposed input (e followed by
+    /// U+0301) is translated as its base letter is
+    pub strip_marks: bool,
+}
+
+
+impl Preset {
+    /// Every grapheme the preset translates: set1 followed by the source
+    /// of each pair.
+    pub fn sources(&self) -> String {
+        let mut sources = self.set1.to_owned();
+
+        sources.extend(self.pairs.iter().map(|(source, _)| *source));
+        sources
+    }
+
+    /// The output of each grapheme of `sources`, in order: set2, padded
+    /// with its last grapheme to the length of set1, followed by the
+    /// target of each pair.
+    pub fn target_list(&self) -> Vec<&'static str> {
+        let set2: Vec<&'static str> = self.set2.as_bytes().graphemes().collect();
+        let padding = set2.last().copied().into_iter().cycle();
+
+        set2.iter().copied().chain(padding)
+            .take(self.set1.as_bytes().graphemes().count())
+            .chain(self.pairs.iter().map(|(_, target)| *target))
+            .collect()
+    }
+
+    /// Every grapheme the preset may produce as a whole target, such as
+    /// the "e" of é. These are the graphemes `-s` squeezes, though never
+    /// within a longer target, such as the "ss" of ß.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let preset = tr::preset::lookup("cyrillic-latin").unwrap();
+    ///
+    /// assert!(preset.targets().contains('B'));
+    /// assert!(!preset.targets().contains('h'));
+    /// ```
+    pub fn targets(&self) -> String {
+        self.target_list().into_iter()
+            .filter(|target| target.as_bytes().graphemes().count() == 1)
+            .collect()
+    }
+
+    /// The grapheme `g` stands for in the mapping: `g` itself, or, if the
+    /// preset strips marks, the letter at its start when only combining
+    /// marks follow it and the letter is ASCII or one the preset
+    /// translates.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let preset = tr::preset::lookup("ascii-fold").unwrap();
+    ///
+    /// assert_eq!("e", preset.unmarked("e\u{301}"));
+    /// assert_eq!("\u{915}\u{94d}", preset.unmarked("\u{915}\u{94d}"));
+    /// ```
+    pub fn unmarked<'a>(&self, g: &'a str) -> &'a str {
+        let base = match g.chars().next() {
+            Some(c) if self.strip_marks => c,
+            _ => return g
+        };
+
+        let (letter, marks) = g.split_at(base.len_utf8());
+        let translated = || self.set1.contains(base) || self.pairs.iter().any(|(s, _)| *s == letter);
+
+        match marks.chars().all(|c| c.general_category_group() == GeneralCategoryGroup::Mark) {
+            true if base.is_ascii_alphabetic() || translated() => letter,
+            _ => g
+        }
+    }
+
+    /// Create the mapping from each translated grapheme to its output.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let map = tr::preset::lookup("german-umlauts").unwrap().mapping();
+    ///
+    /// assert_eq!("ue", map["ü"]);
+    /// ```
+    pub fn mapping(&self) -> HashMap<String, String> {
+        map_expanded_targets(&self.sources(), &self.target_list())
+    }
+}
+
+
+pub const PRESETS: &[Preset] = &[
+    Preset {
+        name: "ascii-fold",
+        description: "strip diacritics from Latin letters (é → e, æ → ae)",
+        set1: concat!(
+            "ÀÁÂÃÄÅÇÈÉÊËÌÍÎÏÐÑÒÓÔÕÖØÙÚÛÜÝ",
+            "àáâãäåçèéêëìíîïðñòóôõöøùúûüýÿ",
+            "ĀāĂăĄąĆćĈĉĊċČčĎďĐđĒēĔĕĖėĘęĚě",
+            "ĜĝĞğĠġĢģĤĥĦħĨĩĪīĬĭĮįİıĴĵĶķ",
+            "ĹĺĻļĽľĿŀŁłŃńŅņŇňŊŋŌōŎŏŐő",
+            "ŔŕŖŗŘřŚśŜŝŞşŠšŢţŤťŦŧ",
+            "ŨũŪūŬŭŮůŰűŲųŴŵŶŷŸŹźŻżŽžſ",
+        ),
+        set2: concat!(
+            "AAAAAACEEEEIIIIDNOOOOOOUUUUY",
+            "aaaaaaceeeeiiiidnoooooouuuuyy",
+            "AaAaAaCcCcCcCcDdDdEeEeEeEeEe",
+            "GgGgGgGgHhHhIiIiIiIiIiJjKk",
+            "LlLlLlLlLlNnNnNnNnOoOoOo",
+            "RrRrRrSsSsSsSsTtTtTt",
+            "UuUuUuUuUuUuWwYyYZzZzZzs",
+        ),
+        pairs: &[
+            ("Æ", "AE"), ("æ", "ae"), ("Œ", "OE"), ("œ", "oe"), ("Ĳ", "IJ"),
+            ("ĳ", "ij"), ("Þ", "TH"), ("þ", "th"), ("ß", "ss"),
+        ],
+        strip_marks: true,
+    },
+    Preset {
+        name: "smart-quotes",
+        description: "replace typographic quotes with ASCII quotes (“ → \")",
+        set1: "‘’‚‛“”„‟",
+        set2: "''''\"\"\"\"",
+        pairs: &[],
+        strip_marks: false,
+    },
+    Preset {
+        name: "fullwidth",
+        description: "replace fullwidth forms with ASCII (Ａ → A)",
+        set1: concat!(
+            "！＂＃＄％＆＇（）＊＋，－．／０１２３４５６７８９：；＜＝＞？＠",
+            "ＡＢＣＤＥＦＧＨＩＪＫＬＭＮＯＰＱＲＳＴＵＶＷＸＹＺ［＼］＾＿｀",
+            "ａｂｃｄｅｆｇｈｉｊｋｌｍｎｏｐｑｒｓｔｕｖｗｘｙｚ｛｜｝～",
+            "\u{3000}",
+        ),
+        set2: concat!(
+            "!\"#$%&'()*+,-./0123456789:;<=>?@",
+            "ABCDEFGHIJKLMNOPQRSTUVWXYZ[\\]^_`",
+            "abcdefghijklmnopqrstuvwxyz{|}~",
+            " ",
+        ),
+        pairs: &[],
+        strip_marks: false,
+    },
+    Preset {
+        name: "dashes",
+        description: "replace Unicode dashes and the minus sign with '-'",
+        set1: "֊־᐀᠆‐‑‒–—―−⸗⸚⸺⸻⹀〜〰゠︱︲﹘﹣－",
+        set2: "-",
+        pairs: &[],
+        strip_marks: false,
+    },
+    Preset {
+        name: "spaces",
+        description: "replace Unicode space separators with ' '",
+        set1: concat!(
+            "\u{a0}\u{1680}\u{2000}\u{2001}\u{2002}\u{2003}\u{2004}\u{2005}",
+            "\u{2006}\u{2007}\u{2008}\u{2009}\u{200a}\u{202f}\u{205f}\u{3000}",
+        ),
+        set2: " ",
+        pairs: &[],
+        strip_marks: false,
+    },
+    Preset {
+        name: "cyrillic-latin",
+        description: "romanize Cyrillic letters (Ж → Zh, Щ → Shch)",
+        set1: "АБВГҐДЕЗИІЙКЛМНОПРСТУЎФЫЭабвгґдезиійклмнопрстуўфыэ",
+        set2: "ABVGGDEZIIYKLMNOPRSTUUFYEabvggdeziiyklmnoprstuufye",
+        pairs: &[
+            ("Ё", "Yo"), ("Є", "Ye"), ("Ж", "Zh"), ("Ї", "Yi"), ("Х", "Kh"),
+            ("Ц", "Ts"), ("Ч", "Ch"), ("Ш", "Sh"), ("Щ", "Shch"), ("Ъ", ""),
+            ("Ь", ""), ("Ю", "Yu"), ("Я", "Ya"),
+            ("ё", "yo"), ("є", "ye"), ("ж", "zh"), ("ї", "yi"), ("х", "kh"),
+            ("ц", "ts"), ("ч", "ch"), ("ш", "sh"), ("щ", "shch"), ("ъ", ""),
+            ("ь", ""), ("ю", "yu"), ("я", "ya"),
+        ],
+        strip_marks: false,
+    },
+    Preset {
+        name: "greek-latin",
+        description: "romanize Greek letters (Θ → Th, ψ → ps)",
+        set1: concat!(
+            "ΑΆΒΓΔΕΈΖΗΉΙΊΪΚΛΜΝΞΟΌΠΡΣΤΥΎΫΦΩΏ",
+            "αάβγδεέζηήιίϊΐκλμνξοόπρσςτυύϋΰφωώ",
+        ),
+        set2: concat!(
+            "AAVGDEEZIIIIIKLMNXOOPRSTYYYFOO",
+            "aavgdeeziiiiiiklmnxooprsstyyyyfoo",
+        ),
+        pairs: &[
+            ("Θ", "Th"), ("Χ", "Ch"), ("Ψ", "Ps"),
+            ("θ", "th"), ("χ", "ch"), ("ψ", "ps"),
+        ],
+        strip_marks: false,
+    },
+    Preset {
+        name: "german-umlauts",
+        description: "expand German umlauts and sharp s (ü → ue, ß → ss)",
+        set1: "",
+        set2: "",
+        pairs: &[
+            ("Ä", "Ae"), ("Ö", "Oe"), ("Ü", "Ue"), ("ẞ", "SS"),
+            ("ä", "ae"), ("ö", "oe"), ("ü", "ue"), ("ß", "ss"),
+        ],
+        strip_marks: false,
+    },
+];
+
+
+/// Find the preset called `name`.
+///
+/// # Examples
+///
+/// ```
+/// let preset = tr::preset::lookup("dashes").unwrap();
+///
+/// assert_eq!("-", preset.set2);
+/// assert!(tr::preset::lookup("no-such-preset").is_none());
+/// ```
+pub fn lookup(name: &str) -> Option<&'static Preset> {
+    PRESETS.iter().find(|preset| preset.name == name)
+}
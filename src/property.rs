@@ -0,0 +1,164 @@
+use unicode_properties::{GeneralCategory, GeneralCategoryGroup, UnicodeEmoji,
+                         UnicodeGeneralCategory};
+use unicode_script::{Script, UnicodeScript};
+
+
+/// A Unicode character property usable as a class in a set.
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub enum Property {
+    Script(Script),
+    Category(GeneralCategory),
+    CategoryGroup(GeneralCategoryGroup),
+    CasedLetter,
+    Emoji,
+}
+
+
+const CATEGORIES: &[(&str, GeneralCategory)] = &[
+    ("Lu", GeneralCategory::UppercaseLetter),
+    ("Ll", GeneralCategory::LowercaseLetter),
+    ("Lt", GeneralCategory::TitlecaseLetter),
+    ("Lm", GeneralCategory::ModifierLetter),
+    ("Lo", GeneralCategory::OtherLetter),
+    ("Mn", GeneralCategory::NonspacingMark),
+    ("Mc", GeneralCategory::SpacingMark),
+    ("Me", GeneralCategory::EnclosingMark),
+    ("Nd", GeneralCategory::DecimalNumber),
+    ("Nl", GeneralCategory::LetterNumber),
+    ("No", GeneralCategory::OtherNumber),
+    ("Pc", GeneralCategory::ConnectorPunctuation),
+    ("Pd", GeneralCategory::DashPunctuation),
+    ("Ps", GeneralCategory::OpenPunctuation),
+    ("Pe", GeneralCategory::ClosePunctuation),
+    ("Pi", GeneralCategory::InitialPunctuation),
+    ("Pf", GeneralCategory::FinalPunctuation),
+    ("Po", GeneralCategory::OtherPunctuation),
+    ("Sm", GeneralCategory::MathSymbol),
+    ("Sc", GeneralCategory::CurrencySymbol),
+    ("Sk", GeneralCategory::ModifierSymbol),
+    ("So", GeneralCategory::OtherSymbol),
+    ("Zs", GeneralCategory::SpaceSeparator),
+    ("Zl", GeneralCategory::LineSeparator),
+    ("Zp", GeneralCategory::ParagraphSeparator),
+    ("Cc", GeneralCategory::Control),
+    ("Cf", GeneralCategory::Format),
+    ("Cs", GeneralCategory::Surrogate),
+    ("Co", GeneralCategory::PrivateUse),
+    ("Cn", GeneralCategory::Unassigned),
+];
+
+
+const CATEGORY_GROUPS: &[(&str, GeneralCategoryGroup)] = &[
+    ("L", GeneralCategoryGroup::Letter),
+    ("M", GeneralCategoryGroup::Mark),
+    ("N", GeneralCategoryGroup::Number),
+    ("P", GeneralCategoryGroup::Punctuation),
+    ("S", GeneralCategoryGroup::Symbol),
+    ("Z", GeneralCategoryGroup::Separator),
+    ("C", GeneralCategoryGroup::Other),
+];
+
+
+fn category(name: &str) -> Option<Property> {
+    if name == "LC" {
+        return Some(Property::CasedLetter);
+    }
+
+    CATEGORIES.iter().find(|(abbr, _)| *abbr == name)
+        .map(|(_, gc)| Property::Category(*gc))
+        .or_else(|| CATEGORY_GROUPS.iter().find(|(abbr, _)| *abbr == name)
+                 .map(|(_, group)| Property::CategoryGroup(*group)))
+}
+
+
+fn script(name: &str) -> Option<Property> {
+    Script::from_full_name(name)
+        .or_else(|| Script::from_short_name(name))
+        .map(Property::Script)
+}
+
+
+impl Property {
+    /// Interpret the name of a property class.
+    ///
+    ///    script=NAME, sc=NAME          characters of the script NAME, given by
+    ///                                  its full (Greek) or short (Grek) name
+    ///    gc=XX, general_category=XX    characters of the general category XX,
+    ///                                  e.g. Lu, Nd, or a group such as L
+    ///    emoji                         emoji
+    ///
+    /// Within `\p{...}`, a bare category or script name is also accepted.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tr::property::Property;
+    ///
+    /// assert!(Property::parse("script=Greek").unwrap().matches('λ'));
+    /// assert!(Property::parse("gc=Lu").unwrap().matches('É'));
+    /// assert!(Property::parse("script=Klingon").is_none());
+    /// ```
+    pub fn parse(name: &str) -> Option<Property> {
+        match name.find('=').map(|i| (&name[..i], &name[i + 1..])) {
+            Some(("script", value)) | Some(("sc", value)) => script(value),
+            Some(("gc", value)) | Some(("general_category", value)) => category(value),
+            Some(_) => None,
+            None if name.eq_ignore_ascii_case("emoji") => Some(Property::Emoji),
+            None => None
+        }
+    }
+
+    /// Interpret the name given in `\p{NAME}`, which may also be a bare
+    /// category or script name.
+    pub fn parse_short(name: &str) -> Option<Property> {
+        Property::parse(name)
+            .or_else(|| category(name))
+            .or_else(|| script(name))
+    }
+
+    /// Test whether `c` has the property.
+    pub fn matches(&self, c: char) -> bool {
+        match self {
+            Property::Script(script) => c.script() == *script,
+            Property::Category(gc) => c.general_category() == *gc,
+            Property::CategoryGroup(group) => c.general_category_group() == *group,
+            Property::CasedLetter => c.is_letter_cased(),
+            Property::Emoji => c.is_emoji_char() && !c.is_emoji_component(),
+        }
+    }
+
+    /// Test whether the grapheme `g` has the property.
+    ///
+    /// A grapheme takes its properties from its first character, so that
+    /// e.g. a letter followed by combining marks is still a letter. Emoji
+    /// are recognized whole: an emoji followed by modifiers, or a keycap
+    /// sequence such as 1️⃣, is an emoji, while a bare digit is not.
+    pub fn contains(&self, g: &str) -> bool {
+        let c = match g.chars().next() {
+            Some(c) => c,
+            None => return false
+        };
+
+        // flags, skin tones, and sequences with an emoji presentation
+        // selector or combining enclosing keycap
+        let sequence = || c.is_emoji_char()
+            && (is_presentation(c) || g.contains(&['\u{fe0f}', '\u{20e3}'][..]));
+
+        self.matches(c) || (*self == Property::Emoji && sequence())
+    }
+
+    /// Iterate over every character with the property, in code point order.
+    pub fn chars(self) -> impl Iterator<Item=char> {
+        (0..=0x10ffff)
+            .filter_map(std::char::from_u32)
+            .filter(move |&c| self.matches(c))
+    }
+}
+
+
+fn is_presentation(c: char) -> bool {
+    use unicode_properties::EmojiStatus::*;
+
+    matches!(c.emoji_status(), EmojiPresentationAndEmojiComponent |
+                               EmojiPresentationAndModifierAndEmojiComponent)
+}
@@ -0,0 +1,47 @@
+use bstr::ByteSlice;
+
+use crate::arg_parser::Config;
+use crate::command::Op;
+use crate::parser::parse;
+
+
+/// The record separator `config` calls for, if any: NUL with
+/// `config.null_data`, otherwise `config.record_separator`, a single
+/// grapheme, which may use the escapes of the set syntax, such as `\0` or
+/// `\036`.
+pub(crate) fn separator(config: &Config) -> Result<Option<String>, String> {
+    let separator = match (config.null_data, config.record_separator.as_str()) {
+        (true, _) => return Ok(Some("\0".to_owned())),
+        (false, "") => return Ok(None),
+        (false, s) => parse(s)?
+    };
+
+    match separator.as_bytes().graphemes().count() {
+        1 => Ok(Some(separator.into_owned())),
+        _ => Err("the record separator must be a single character".to_owned())
+    }
+}
+
+
+/// Divide the input into records ended by the separator `config` calls
+/// for, protecting the separator from `op`.
+///
+/// Each separator is written unchanged, whatever `op` would make of it, and
+/// is passed to `op` as a boundary; see `crate::command::Op`. If `config`
+/// calls for no separator, `op` is returned unchanged.
+pub fn records(mut op: Op, config: &Config) -> Result<Op, String> {
+    let separator = match separator(config)? {
+        Some(separator) => separator,
+        None => return Ok(op)
+    };
+
+    Ok(Box::new(move |b| {
+        match b == separator {
+            true => {
+                op("");
+                Some(b.to_owned())
+            },
+            false => op(b)
+        }
+    }))
+}
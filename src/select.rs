@@ -0,0 +1,165 @@
+use bstr::ByteSlice;
+use regex::Regex;
+
+use crate::arg_parser::Config;
+use crate::command::Op;
+use crate::fields::Fields;
+use crate::record::separator;
+
+
+fn regex(pattern: &str) -> Result<Regex, String> {
+    Regex::new(pattern).map_err(|_| format!("invalid regular expression ‘{}’", pattern))
+}
+
+
+/// Restrict `op` to the lines of the input selected by `config`:
+///
+///    --lines LIST           lines numbered in LIST, as for `--fields`
+///    --match REGEX          lines matching REGEX
+///    --between START END    each line matching START, through the next
+///                           line after it matching END, as in sed
+///
+/// A line must satisfy every selector given. If none is given, `op` is
+/// returned unchanged. Where `config` calls for a record separator, as
+/// with `-z`, each record is a line.
+///
+/// Unless only `--lines` is given, each line is read in full before it is
+/// selected, so the graphemes of a selected line are passed to `op` and
+/// its output returned once the line ends. Other lines are written unchanged if `echo` is set, as when `op`
+/// transforms the input rather than reporting on it, and each is passed
+/// to `op` as a boundary (see `crate::command::Op`), so that an op that
+/// counts lines still sees every one.
+pub fn select(mut op: Op, config: &Config, echo: bool) -> Result<Op, String> {
+    if config.lines.is_empty() && config.pattern.is_empty() && config.between.is_none() {
+        return Ok(op);
+    }
+
+    // line lists share their syntax, and so their errors, with field lists
+    let lines = match config.lines.as_str() {
+        "" => None,
+        list => Some(Fields::parse(list).map_err(|e| e.replace("field", "line"))?)
+    };
+
+    let pattern = match config.pattern.as_str() {
+        "" => None,
+        pattern => Some(regex(pattern)?)
+    };
+
+    let between = match &config.between {
+        Some((start, end)) => Some((regex(start)?, regex(end)?)),
+        None => None
+    };
+
+    let separator = separator(config)?.unwrap_or_else(|| "\n".to_owned());
+
+    let lines = match lines {
+        Some(lines) if pattern.is_none() && between.is_none() => {
+            return Ok(select_numbered(op, lines, separator, echo));
+        },
+        lines => lines
+    };
+
+    let mut line = String::new();
+    let mut number = 1;
+    let mut inside = false;
+
+    Ok(Box::new(move |b| {
+        line.push_str(b);
+
+        // a line may end in "\r\n", a single grapheme
+        if !b.is_empty() && !b.ends_with(separator.as_str()) {
+            return None;
+        }
+
+        let mut output = String::new();
+        let mut write = |c: Option<String>| if let Some(c) = c {
+            output.push_str(&c);
+        };
+
+        if line.is_empty() {
+            write(op(b));
+            return Some(output);
+        }
+
+        let text = match separator.as_str() {
+            "\n" => line.trim_end_matches(&['\r', '\n'][..]),
+            separator => line.strip_suffix(separator).unwrap_or(&line)
+        };
+
+        let in_range = match &between {
+            Some((_, end)) if inside => {
+                inside = !end.is_match(text);
+                true
+            },
+            Some((start, _)) => {
+                inside = start.is_match(text);
+                inside
+            },
+            None => true
+        };
+
+        let selected = in_range
+            && lines.as_ref().is_none_or(|lines| lines.contains(number))
+            && pattern.as_ref().is_none_or(|pattern| pattern.is_match(text));
+
+        match selected {
+            true => {
+                for g in line.as_bytes().graphemes() {
+                    write(op(g));
+                }
+
+                if b.is_empty() {
+                    write(op(""));
+                }
+            },
+            false => {
+                if echo {
+                    write(Some(line.clone()));
+                }
+
+                write(op(""));
+            }
+        }
+
+        line.clear();
+        number += 1;
+
+        Some(output)
+    }))
+}
+
+
+/// Restrict `op` to the lines numbered in `lines`, ended by `separator`,
+/// as `select` does, but passing each grapheme on as it comes: whether a
+/// line is selected is known from its number as it starts.
+fn select_numbered(mut op: Op, lines: Fields, separator: String, echo: bool) -> Op {
+    let mut number = 1;
+
+    Box::new(move |b| {
+        if b.is_empty() {
+            return op(b);
+        }
+
+        let ends = b.ends_with(separator.as_str());
+
+        let output = match lines.contains(number) {
+            true => op(b),
+            false => {
+                let mut output = match echo {
+                    true => b.to_owned(),
+                    false => String::new()
+                };
+
+                if ends {
+                    output.extend(op(""));
+                }
+
+                Some(output)
+            }
+        };
+
+        number += ends as usize;
+
+        output
+    })
+}
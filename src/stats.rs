@@ -0,0 +1,127 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use bstr::ByteSlice;
+
+use crate::bytes;
+use crate::command::Op;
+use crate::explain::describe;
+use crate::parser::CharSet;
+
+
+/// Counts gathered while processing, for `--stats`.
+#[derive(Debug,Default)]
+pub struct Stats {
+    pub bytes_read: usize,
+    pub bytes_written: usize,
+    pub graphemes_read: usize,
+    pub graphemes_written: usize,
+    pub translated: usize,
+    pub deleted: usize,
+    pub squeezed: usize,
+    /// How often each member of SET1 occurs in the input, when requested.
+    pub frequencies: HashMap<String, usize>,
+}
+
+
+/// Stats shared among the ops that update them.
+pub type Counter = Rc<RefCell<Stats>>;
+
+
+/// What an op does to the graphemes it acts on.
+#[derive(Debug,Clone,Copy)]
+pub enum Effect {
+    Translate,
+    Delete,
+    Squeeze,
+}
+
+
+/// Wrap `op`, counting the graphemes it acts on in `counter`.
+///
+/// A grapheme is counted as translated if `op` replaces it with anything
+/// else, and as deleted or squeezed if `op` writes nothing in its place.
+pub fn count(mut op: Op, counter: &Counter, effect: Effect) -> Op {
+    let counter = Rc::clone(counter);
+
+    Box::new(move |b| {
+        let output = op(b);
+        let mut stats = counter.borrow_mut();
+
+        match (effect, &output) {
+            _ if b.is_empty() => (),
+            (Effect::Translate, Some(c)) if c != b => stats.translated += 1,
+            (Effect::Delete, None) => stats.deleted += 1,
+            (Effect::Squeeze, None) => stats.squeezed += 1,
+            _ => ()
+        }
+
+        output
+    })
+}
+
+
+/// Wrap `op`, counting the graphemes and bytes it reads and writes in
+/// `counter`, and, if `set1` is given, how often each of its members is
+/// read, or, if `complement` is `true`, each grapheme that is not.
+///
+/// In `--bytes` mode, as `bytes` is `true`, bytes are counted as they
+/// are read and written, not as the characters they are decoded to.
+pub fn measure(mut op: Op, counter: &Counter, set1: Option<CharSet>, complement: bool, bytes: bool) -> Op {
+    let counter = Rc::clone(counter);
+
+    Box::new(move |b| {
+        let output = op(b);
+        let mut stats = counter.borrow_mut();
+
+        stats.bytes_read += match bytes {
+            true => b.chars().count(),
+            false => b.len()
+        };
+        stats.graphemes_read += !b.is_empty() as usize;
+
+        if let Some(c) = &output {
+            stats.bytes_written += match bytes {
+                true => bytes::encode(c).len(),
+                false => c.len()
+            };
+            stats.graphemes_written += c.as_bytes().graphemes().count();
+        }
+
+        if !b.is_empty() && set1.as_ref().is_some_and(|set| set.contains(b) != complement) {
+            *stats.frequencies.entry(b.to_owned()).or_insert(0) += 1;
+        }
+
+        output
+    })
+}
+
+
+impl Stats {
+    /// Format the stats for display, one count per line, followed by the
+    /// frequencies of SET1 members if any were gathered, most frequent
+    /// first.
+    pub fn report(&self) -> String {
+        let mut lines = vec![
+            format!("bytes read: {}", self.bytes_read),
+            format!("bytes written: {}", self.bytes_written),
+            format!("graphemes read: {}", self.graphemes_read),
+            format!("graphemes written: {}", self.graphemes_written),
+            format!("translated: {}", self.translated),
+            format!("deleted: {}", self.deleted),
+            format!("squeezed: {}", self.squeezed),
+        ];
+
+        if !self.frequencies.is_empty() {
+            let mut frequencies: Vec<_> = self.frequencies.iter().collect();
+
+            frequencies.sort_by(|(g1, n1), (g2, n2)| n2.cmp(n1).then(g1.cmp(g2)));
+
+            lines.push("SET1 frequencies:".to_owned());
+            lines.extend(frequencies.iter().map(|(g, n)| format!("  {}: {}", describe(g), n)));
+        }
+
+        lines.iter().map(|line| format!("{}\n", line)).collect()
+    }
+}
@@ -96,7 +96,7 @@ fn unknown_switch_in_combo_should_indicate_correct_invalid_option() {
 fn double_dash_should_end_option_processing() {
     let config = parse_args(&["tr", "--", "-s", ".S"]).unwrap();
 
-    assert_eq!(config.squeeze, false);
+    assert!(!config.squeeze);
     assert_eq!(config.set1, "-s");
 }
 
@@ -140,3 +140,289 @@ fn combined_squeeze_and_delete_with_set2_should_be_valid() {
     assert!(config.squeeze);
     assert!(config.delete);
 }
+
+
+#[test]
+fn preset_should_stand_in_for_sets() {
+    let config = parse_args(&["tr", "--preset", "dashes"]).unwrap();
+
+    assert_eq!(config.preset, "dashes");
+    assert!(config.set1.is_empty());
+}
+
+
+#[test]
+fn preset_should_accept_inline_name() {
+    let config = parse_args(&["tr", "-s", "--preset=spaces"]).unwrap();
+
+    assert!(config.squeeze);
+    assert_eq!(config.preset, "spaces");
+}
+
+
+#[test]
+fn preset_with_set_should_produce_error() {
+    assert_eq!(parse_args(&["tr", "--preset", "dashes", "a"]).unwrap_err(),
+               "extra operand ‘a’");
+}
+
+
+#[test]
+fn canonicalize_should_stand_in_for_sets() {
+    let config = parse_args(&["tr", "--canonicalize", "a-z"]).unwrap();
+
+    assert_eq!(config.canonicalize.as_deref(), Some("a-z"));
+    assert!(config.set1.is_empty());
+    assert_eq!(parse_args(&["tr", "--canonicalize=a-z", "b"]).unwrap_err(), "extra operand ‘b’");
+}
+
+
+#[test]
+fn unknown_preset_should_produce_error() {
+    assert_eq!(parse_args(&["tr", "--preset", "nope"]).unwrap_err(),
+               "unknown preset ‘nope’");
+}
+
+
+#[test]
+fn preset_without_name_should_produce_error() {
+    assert_eq!(parse_args(&["tr", "--preset"]).unwrap_err(),
+               "option '--preset' requires an argument");
+}
+
+
+#[test]
+fn extended_flag_should_select_extended_syntax() {
+    let config = parse_args(&["tr", "-E", "-d", "[:alpha:]--[aeiou]"]).unwrap();
+
+    assert!(config.extended);
+    assert!(parse_args(&["tr", "--extended", "-d", "a"]).unwrap().extended);
+    assert!(!parse_args(&["tr", "-d", "a"]).unwrap().extended);
+}
+
+
+#[test]
+fn each_e_should_begin_a_stage() {
+    let config = parse_args(&["tr", "-e", "A-Z", "a-z", "-e", "-d", "[:punct:]", "-e", "-s", " "]).unwrap();
+
+    assert_eq!(config.stages.len(), 3);
+    assert_eq!(config.stages[0].set2, "a-z");
+    assert!(config.stages[1].delete);
+    assert_eq!(config.stages[1].set1, "[:punct:]");
+    assert!(config.stages[2].squeeze);
+    assert!(!config.stages[2].delete);
+}
+
+
+#[test]
+fn options_before_first_stage_should_apply_to_every_stage() {
+    let config = parse_args(&["tr", "-E", "-e", "a", "b", "-e", "-d", "c"]).unwrap();
+
+    assert!(config.stages.iter().all(|stage| stage.extended));
+}
+
+
+#[test]
+fn incomplete_stage_should_produce_error() {
+    let err = parse_args(&["tr", "-e", "abc", "-e", "-d", "x"]).unwrap_err();
+
+    assert_eq!(err, "missing operand after ‘abc’");
+}
+
+
+#[test]
+fn e_should_be_set2_outside_stages_only_after_double_dash() {
+    let config = parse_args(&["tr", "--", "abc", "-e"]).unwrap();
+
+    assert!(config.stages.is_empty());
+    assert_eq!(config.set2, "-e");
+    assert_eq!(parse_args(&["tr", "abc", "-e"]).unwrap_err(), "invalid option -- 'e'");
+}
+
+
+#[test]
+fn check_should_take_set1_alone() {
+    assert!(parse_args(&["tr", "--check", "\t"]).unwrap().check);
+    assert_eq!(parse_args(&["tr", "--check", "a", "b"]).unwrap_err(), "extra operand ‘b’");
+}
+
+
+#[test]
+fn fields_should_take_argument() {
+    let config = parse_args(&["tr", "--fields", "2-3", "--delimiter", ",", "--csv", "a", "b"]).unwrap();
+
+    assert_eq!(config.fields, "2-3");
+    assert_eq!(config.delimiter, ",");
+    assert!(config.csv);
+    assert_eq!(parse_args(&["tr", "--fields"]).unwrap_err(), "option '--fields' requires an argument");
+}
+
+
+#[test]
+fn json_should_not_combine_with_fields() {
+    let config = parse_args(&["tr", "--json", "--json-keys", "a", "b"]).unwrap();
+
+    assert!(config.json && config.json_keys);
+    assert_eq!(parse_args(&["tr", "--json", "--fields", "2", "a", "b"]).unwrap_err(),
+               "--fields cannot be combined with --json");
+}
+
+
+#[test]
+fn check_should_not_combine_with_restrictions() {
+    assert!(parse_args(&["tr", "--check", "--lines", "2", "a"]).is_ok());
+    assert_eq!(parse_args(&["tr", "--check", "--fields", "2", "a"]).unwrap_err(),
+               "--fields cannot be combined with --check");
+    assert_eq!(parse_args(&["tr", "--check", "--json", "a"]).unwrap_err(),
+               "--json cannot be combined with --check");
+    assert_eq!(parse_args(&["tr", "-z", "--check", "a"]).unwrap_err(),
+               "--null-data cannot be combined with --check");
+}
+
+
+#[test]
+fn selectors_should_take_arguments() {
+    let config = parse_args(&["tr", "--lines=2-", "--match", "^a", "--between", "x", "y", "a", "b"]).unwrap();
+
+    assert_eq!(config.lines, "2-");
+    assert_eq!(config.pattern, "^a");
+    assert_eq!(config.between, Some(("x".to_owned(), "y".to_owned())));
+    assert_eq!(parse_args(&["tr", "--match"]).unwrap_err(), "option '--match' requires an argument");
+    assert_eq!(parse_args(&["tr", "--between", "x"]).unwrap_err(), "option '--between' requires two arguments");
+}
+
+
+#[test]
+fn record_options_should_be_recognised() {
+    let config = parse_args(&["tr", "-zd", "--record-separator=\\036", "a"]).unwrap();
+
+    assert!(config.null_data && config.delete);
+    assert_eq!(config.record_separator, "\\036");
+    assert_eq!(parse_args(&["tr", "--record-separator"]).unwrap_err(),
+               "option '--record-separator' requires an argument");
+}
+
+
+#[test]
+fn buffering_options_should_be_recognised() {
+    assert!(parse_args(&["tr", "-u", "a", "b"]).unwrap().unbuffered);
+    assert!(parse_args(&["tr", "--unbuffered", "a", "b"]).unwrap().unbuffered);
+    assert!(parse_args(&["tr", "--line-buffered", "a", "b"]).unwrap().line_buffered);
+}
+
+
+#[test]
+fn follow_should_take_argument() {
+    assert_eq!(parse_args(&["tr", "--follow", "app.log", "-d", "a"]).unwrap().follow, std::path::Path::new("app.log"));
+    assert_eq!(parse_args(&["tr", "--follow"]).unwrap_err(), "option '--follow' requires an argument");
+}
+
+
+#[test]
+fn long_options_should_accept_unambiguous_prefixes() {
+    let config = parse_args(&["tr", "--comp", "--squ", "--fie=2", "--deli", ",", "a", "b"]).unwrap();
+
+    assert!(config.complement && config.squeeze);
+    assert_eq!(config.fields, "2");
+    assert_eq!(config.delimiter, ",");
+}
+
+
+#[test]
+fn gnu_options_should_win_shared_prefixes() {
+    assert!(parse_args(&["tr", "--del", "a"]).unwrap().delete);
+    assert!(parse_args(&["tr", "--d", "a"]).unwrap().delete);
+    assert!(parse_args(&["tr", "--s", "a"]).unwrap().squeeze);
+}
+
+
+#[test]
+fn ambiguous_prefix_should_list_possibilities() {
+    assert_eq!(parse_args(&["tr", "--pre", "a"]).unwrap_err(),
+               "option '--pre' is ambiguous; possibilities: '--preset' '--preview'");
+}
+
+
+#[test]
+fn exact_name_should_win_over_longer_options() {
+    let config = parse_args(&["tr", "--json", "a", "b"]).unwrap();
+
+    assert!(config.json && !config.json_keys);
+}
+
+
+#[test]
+fn argument_to_flag_should_produce_error() {
+    assert_eq!(parse_args(&["tr", "--delete=x", "a"]).unwrap_err(),
+               "option '--delete' doesn't allow an argument");
+    assert_eq!(parse_args(&["tr", "--stats=all", "a", "b"]).unwrap_err(),
+               "invalid argument ‘all’ for ‘--stats’");
+}
+
+
+#[test]
+fn options_should_be_permuted_after_operands() {
+    let config = parse_args(&["tr", "abc", "-d"]).unwrap();
+
+    assert!(config.delete);
+    assert_eq!(config.set1, "abc");
+
+    let config = parse_args(&["tr", "a", "--preset", "dashes", "-s"]);
+    assert_eq!(config.unwrap_err(), "extra operand ‘a’");
+
+    let config = parse_args(&["tr", "a", "b", "--", "-x"]);
+    assert_eq!(config.unwrap_err(), "extra operand ‘-x’");
+}
+
+
+#[test]
+fn compat_should_select_dialect() {
+    use tr::dialect::Dialect;
+
+    assert_eq!(parse_args(&["tr", "a", "b"]).unwrap().dialect, Dialect::Gnu);
+    assert_eq!(parse_args(&["tr", "--compat=bsd", "a", "b"]).unwrap().dialect, Dialect::Bsd);
+    assert_eq!(parse_args(&["tr", "--compat", "posix", "a", "b"]).unwrap().dialect, Dialect::Posix);
+    assert_eq!(parse_args(&["tr", "--compat=sysv", "a", "b"]).unwrap_err(),
+               "invalid argument ‘sysv’ for ‘--compat’; valid arguments are ‘gnu’, ‘bsd’ and ‘posix’");
+}
+
+
+#[test]
+fn compat_should_not_take_prefix_of_complement() {
+    let config = parse_args(&["tr", "--comp", "--compa=bsd", "a", "b"]).unwrap();
+
+    assert!(config.complement);
+    assert_eq!(config.dialect, tr::dialect::Dialect::Bsd);
+}
+
+
+#[test]
+fn compat_bsd_should_stop_options_at_first_operand() {
+    assert_eq!(parse_args(&["tr", "--compat=bsd", "abc", "-d"]).unwrap().set2, "-d");
+    assert_eq!(parse_args(&["tr", "--compat=bsd", "a", "b", "-d"]).unwrap_err(), "extra operand ‘-d’");
+}
+
+
+#[test]
+fn compat_posix_should_reject_extensions() {
+    assert!(parse_args(&["tr", "--compat=posix", "-cCds", "a", "b"]).is_ok());
+    assert_eq!(parse_args(&["tr", "--compat=posix", "-dt", "a"]).unwrap_err(),
+               "option '-t' is not in POSIX tr; it cannot be used with --compat=posix");
+    assert_eq!(parse_args(&["tr", "--compat=posix", "--delete", "a"]).unwrap_err(),
+               "option '--delete' is not in POSIX tr; it cannot be used with --compat=posix");
+    assert_eq!(parse_args(&["tr", "--compat=posix", "-e", "a", "b"]).unwrap_err(),
+               "option '-e' is not in POSIX tr; it cannot be used with --compat=posix");
+}
+
+
+#[test]
+fn upper_c_should_order_complement_by_collation() {
+    let config = parse_args(&["tr", "-c", "a", "b"]).unwrap();
+    assert!(config.complement && !config.collate);
+
+    let config = parse_args(&["tr", "--complement", "a", "b"]).unwrap();
+    assert!(config.complement && !config.collate);
+
+    let config = parse_args(&["tr", "-C", "a", "b"]).unwrap();
+    assert!(config.complement && config.collate);
+}
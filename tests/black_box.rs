@@ -1,5 +1,6 @@
 
-use std::io::Cursor;
+use std::ffi::OsStr;
+use std::io::{sink, Cursor};
 
 use tr::command::tr;
 
@@ -7,12 +8,12 @@ use tr::command::tr;
 fn _tr<A>(args: A, stdin: &str) -> String
 where
     A: IntoIterator,
-    A::Item: AsRef<str>
+    A::Item: AsRef<OsStr>
 {
     let stdin = Cursor::new(stdin);
     let mut stdout = Vec::new();
 
-    tr(args, stdin, &mut stdout);
+    tr(args, stdin, &mut stdout, &mut sink()).unwrap();
 
     String::from_utf8(stdout).expect("Not UTF-8")
 }
@@ -51,7 +52,6 @@ fn should_expand_simple_repeat() {
 
 
 #[test]
-#[ignore]  // FIXME: handle repeats
 fn should_pad_last_of_set2_to_length_of_set1() {
     let output = _tr(vec!["tr", "[:space:]", "\n"], "                   .");
 
@@ -60,7 +60,6 @@ fn should_pad_last_of_set2_to_length_of_set1() {
 
 
 #[test]
-#[ignore]  // FIXME: handle repeats
 fn should_pad_set2_with_interior_repeat() {
     let output = _tr(vec!["tr", "a-h", "a[.*]h"], "abcdefgh");
 
@@ -69,7 +68,6 @@ fn should_pad_set2_with_interior_repeat() {
 
 
 #[test]
-#[ignore]  // FIXME: handle repeats
 fn should_pad_set2_with_mixed_explicit_and_implicit_repeat() {
     let output = _tr(vec!["tr", "qwertyuiop", "[.*3]a"], "qwertyuiop");
 
@@ -78,11 +76,10 @@ fn should_pad_set2_with_mixed_explicit_and_implicit_repeat() {
 
 
 #[test]
-#[ignore]  // FIXME: handle repeats
 fn should_pad_set2_with_repeat_length_specified_in_octal() {
     let output = _tr(vec!["tr", "0-9a-f", "[.*010]89abcdef"], "0123456789abcdef");
 
-    assert_eq!(output, ".......89abcdef");
+    assert_eq!(output, "........89abcdef");
 }
 
 
@@ -180,3 +177,628 @@ fn should_perform_complement_squeeze() {
 
     assert_eq!(output, "aasdf");
 }
+
+
+#[test]
+fn should_squeeze_translated_output() {
+    let output = _tr(vec!["tr", "-s", "a-z", "A-Z"], "aabbcc  dd");
+
+    assert_eq!(output, "ABC  D");
+}
+
+
+#[test]
+fn truncate_should_leave_extra_set1_untranslated() {
+    let output = _tr(vec!["tr", "-t", "abc", "x"], "abc");
+
+    assert_eq!(output, "xbc");
+}
+
+
+#[test]
+fn ascii_fold_preset_should_strip_diacritics() {
+    let output = _tr(vec!["tr", "--preset", "ascii-fold"], "Crème Brûlée, Łódź");
+
+    assert_eq!(output, "Creme Brulee, Lodz");
+}
+
+
+#[test]
+fn ascii_fold_preset_should_strip_decomposed_diacritics() {
+    let output = _tr(vec!["tr", "--preset", "ascii-fold"], "Cre\u{300}me Bru\u{302}le\u{301}e, \u{915}\u{94d}");
+
+    assert_eq!(output, "Creme Brulee, \u{915}\u{94d}");
+}
+
+
+#[test]
+fn ascii_fold_preset_should_strip_decomposed_diacritics_in_every_mode() {
+    let input = "Cre\u{300}e\u{301}me";
+
+    assert_eq!(_tr(vec!["tr", "-e", "--preset", "ascii-fold", "-e", "a", "b"], input), "Creeme");
+    assert_eq!(_tr(vec!["tr", "-e", "m", "n", "-e", "--preset", "ascii-fold"], input), "Creene");
+    assert_eq!(_tr(vec!["tr", "-s", "--preset", "ascii-fold"], input), "Creme");
+    assert_eq!(_tr(vec!["tr", "-d", "--preset", "ascii-fold"], input), "Crme");
+}
+
+
+#[test]
+fn smart_quotes_preset_should_produce_ascii_quotes() {
+    let output = _tr(vec!["tr", "--preset=smart-quotes"], "“It’s ‘fine’”");
+
+    assert_eq!(output, "\"It's 'fine'\"");
+}
+
+
+#[test]
+fn fullwidth_preset_should_produce_ascii() {
+    let output = _tr(vec!["tr", "--preset", "fullwidth"], "ＡＢＣ－１２３！");
+
+    assert_eq!(output, "ABC-123!");
+}
+
+
+#[test]
+fn dashes_preset_should_pad_to_hyphen_minus() {
+    let output = _tr(vec!["tr", "--preset", "dashes"], "1–2 — 3−4");
+
+    assert_eq!(output, "1-2 - 3-4");
+}
+
+
+#[test]
+fn preset_should_combine_with_delete() {
+    let output = _tr(vec!["tr", "-d", "--preset", "smart-quotes"], "“quoted”");
+
+    assert_eq!(output, "quoted");
+}
+
+
+#[test]
+fn preset_should_combine_with_squeeze() {
+    let output = _tr(vec!["tr", "-s", "--preset", "spaces"], "a\u{a0}\u{2009} b");
+
+    assert_eq!(output, "a b");
+}
+
+
+#[test]
+fn cyrillic_preset_should_romanize_to_several_letters() {
+    let output = _tr(vec!["tr", "--preset", "cyrillic-latin"], "Щука, Жанна, Юрьев");
+
+    assert_eq!(output, "Shchuka, Zhanna, Yurev");
+}
+
+
+#[test]
+fn greek_preset_should_romanize() {
+    let output = _tr(vec!["tr", "--preset", "greek-latin"], "Ψυχή Θεσσαλονίκη");
+
+    assert_eq!(output, "Psychi Thessaloniki");
+}
+
+
+#[test]
+fn german_preset_should_expand_umlauts() {
+    let output = _tr(vec!["tr", "--preset", "german-umlauts"], "Grüße aus Köln");
+
+    assert_eq!(output, "Gruesse aus Koeln");
+}
+
+
+#[test]
+fn ascii_fold_preset_should_expand_ligatures() {
+    let output = _tr(vec!["tr", "--preset", "ascii-fold"], "Æsop’s œuvre");
+
+    assert_eq!(output, "AEsop’s oeuvre");
+}
+
+
+#[test]
+fn squeeze_should_leave_romanized_output_whole() {
+    let output = _tr(vec!["tr", "-s", "--preset", "german-umlauts"], "Fußball");
+
+    assert_eq!(output, "Fussball");
+
+    let output = _tr(vec!["tr", "-s", "--preset", "cyrillic-latin"], "ААбщщ");
+
+    assert_eq!(output, "Abshchshch");
+
+    // ś → s makes s a whole target, but not the ss of ß, while ł → l
+    // makes l one, so ll is squeezed
+    let output = _tr(vec!["tr", "-s", "--preset", "ascii-fold"], "Fußball, śś");
+
+    assert_eq!(output, "Fussbal, s");
+}
+
+
+#[test]
+fn should_translate_named_characters() {
+    let output = _tr(vec!["tr", r"\N{NO-BREAK SPACE}\u2014", r" \x2d"], "a\u{a0}b\u{2014}c");
+
+    assert_eq!(output, "a b-c");
+}
+
+
+#[test]
+fn should_delete_escaped_unicode_range() {
+    let output = _tr(vec!["tr", "-d", r"\u{400}-\u{4FF}"], "Moscow (Москва)");
+
+    assert_eq!(output, "Moscow ()");
+}
+
+
+#[test]
+fn should_delete_script_class() {
+    let output = _tr(vec!["tr", "-d", "[:script=Greek:]"], "alpha (α), beta (β)");
+
+    assert_eq!(output, "alpha (), beta ()");
+}
+
+
+#[test]
+fn should_delete_emoji_but_not_digits() {
+    let output = _tr(vec!["tr", "-d", "[:emoji:]"], "2 👍🏽 or 3 ❤️!");
+
+    assert_eq!(output, "2  or 3 !");
+}
+
+
+#[test]
+fn should_translate_property_escape() {
+    let output = _tr(vec!["tr", r"\p{Nd}", "#"], "1 + ٢ = 3");
+
+    assert_eq!(output, "# + # = #");
+}
+
+
+#[test]
+fn should_delete_consonants_with_extended_set_difference() {
+    let output = _tr(vec!["tr", "-E", "-d", "[:alpha:]--[aeiou]"], "hello world");
+
+    assert_eq!(output, "eo o");
+}
+
+
+#[test]
+fn should_translate_extended_set_intersection() {
+    let output = _tr(vec!["tr", "-E", "[:punct:]&&[!-/]", "#"], "a-b_c.d!");
+
+    assert_eq!(output, "a#b_c#d#");
+}
+
+
+#[test]
+fn should_apply_stages_in_order() {
+    let output = _tr(vec!["tr", "-e", "[:upper:]", "[:lower:]", "-e", "-d", "[:punct:]", "-e", "-s", " "],
+                     "Hello, World!!  Again.");
+
+    assert_eq!(output, "hello world again");
+}
+
+
+#[test]
+fn composed_translation_stages_should_match_sequential_translation() {
+    let staged = _tr(vec!["tr", "-e", "abc", "bcd", "-e", "--preset", "german-umlauts", "-e", "d-z", "D-Z"],
+                     "abcdü xyz");
+
+    assert_eq!(staged, "bcDDUE XYZ");
+}
+
+
+#[test]
+fn check_should_report_each_occurrence_and_fail() {
+    let mut output = Vec::new();
+    let input = Cursor::new("plain\nsmart “quotes”\ttab\n");
+
+    let result = tr(vec!["tr", "--check", r"“”\t"], input, &mut output, &mut sink());
+
+    assert_eq!(String::from_utf8(output).unwrap(),
+               "2:7: U+201C LEFT DOUBLE QUOTATION MARK\n\
+                2:14: U+201D RIGHT DOUBLE QUOTATION MARK\n\
+                2:15: U+0009 <control>\n");
+    assert_eq!(result.unwrap_err(), "check failed: 3 graphemes found");
+}
+
+
+#[test]
+fn check_should_pass_clean_input_silently() {
+    let output = _tr(vec!["tr", "--check", r"\N{NO-BREAK SPACE}"], "nothing to see\n");
+
+    assert_eq!(output, "");
+}
+
+
+#[test]
+fn check_should_honour_complement() {
+    let mut output = Vec::new();
+
+    let result = tr(vec!["tr", "--check", "-c", r"[:print:]\n"], Cursor::new("ok\nno\u{202e}\n"), &mut output, &mut sink());
+
+    assert_eq!(String::from_utf8(output).unwrap(), "2:3: U+202E RIGHT-TO-LEFT OVERRIDE\n");
+    assert!(result.is_err());
+}
+
+
+#[test]
+fn diff_should_show_changed_lines_and_write_nothing_else() {
+    let output = _tr(vec!["tr", "--diff", "-d", "!"], "calm\nloud!\n");
+
+    assert!(output.starts_with("2: loud"));
+    assert!(!output.contains("calm"));
+}
+
+
+#[test]
+fn should_translate_only_selected_fields() {
+    let output = _tr(vec!["tr", "--fields", "2", "a-z", "A-Z"], "id\tname\n1\tann lee\n");
+
+    assert_eq!(output, "id\tNAME\n1\tANN LEE\n");
+}
+
+
+#[test]
+fn should_respect_delimiter_and_pass_other_fields_through() {
+    let output = _tr(vec!["tr", "--delimiter=;", "--fields=2", "-d", "[:digit:]"], "a1;b2;c3\n");
+
+    assert_eq!(output, "a1;b;c3\n");
+}
+
+
+#[test]
+fn should_not_squeeze_across_fields() {
+    let output = _tr(vec!["tr", "--fields", "1-", "-s", "x"], "axx\txxb\n");
+
+    assert_eq!(output, "ax\txb\n");
+}
+
+
+#[test]
+fn should_keep_quoted_csv_fields_intact() {
+    let output = _tr(vec!["tr", "--csv", "--fields", "2", "-d", "[:digit:]"],
+                     "name,phone\nann,\"(555) 123,4567\"\n\"lee, \"\"jr\"\"\",\"42\"\n");
+
+    assert_eq!(output, "name,phone\nann,\"() ,\"\n\"lee, \"\"jr\"\"\",\"\"\n");
+}
+
+
+#[test]
+fn json_should_transform_string_values_only() {
+    let output = _tr(vec!["tr", "--json", "-s", " "], "{\"a  b\": \"x  \\\"y\\\"  z\"}\n");
+
+    assert_eq!(output, "{\"a  b\": \"x \\\"y\\\" z\"}\n");
+}
+
+
+#[test]
+fn lines_should_restrict_op_to_numbered_lines() {
+    let output = _tr(vec!["tr", "--lines", "2-3", "a-z", "A-Z"], "head\nbody\nbody\ntail");
+
+    assert_eq!(output, "head\nBODY\nBODY\ntail");
+}
+
+
+#[test]
+fn match_should_restrict_op_to_matching_lines() {
+    let output = _tr(vec!["tr", "--match", "^#", "-d", "#"], "# a\nb # c\n## d\n");
+
+    assert_eq!(output, " a\nb # c\n d\n");
+}
+
+
+#[test]
+fn between_should_select_ranges_inclusively() {
+    let output = _tr(vec!["tr", "--between", "BEGIN", "END", "a-z", "A-Z"],
+                     "x\nBEGIN x\nx\nEND x\nx\nBEGIN\nx");
+
+    assert_eq!(output, "x\nBEGIN X\nX\nEND X\nx\nBEGIN\nX");
+}
+
+
+#[test]
+fn squeeze_should_reset_between_selected_regions() {
+    let output = _tr(vec!["tr", "--match", "^$", "-s", "\n"], "a\n\n\nb\n\nc\n");
+
+    assert_eq!(output, "a\n\nb\n\nc\n");
+}
+
+
+#[test]
+fn diff_and_check_should_number_lines_despite_selection() {
+    let diff = _tr(vec!["tr", "--diff", "--lines", "2-", "-d", "!"], "a!\nb!\n");
+    let mut check = Vec::new();
+    let result = tr(vec!["tr", "--check", "--lines", "2", "!"], Cursor::new("a!\nb!"), &mut check, &mut sink());
+
+    assert!(diff.starts_with("2: b"));
+    assert!(!diff.contains("a!"));
+    assert_eq!(String::from_utf8(check).unwrap(), "2:2: U+0021 EXCLAMATION MARK\n");
+    assert_eq!(result.unwrap_err(), "check failed: 1 grapheme found");
+}
+
+
+#[test]
+fn null_data_should_protect_nul_and_reset_squeeze() {
+    let output = _tr(vec!["tr", "-z", "-s", "x\\0"], "axx\0xxb\0\0");
+
+    assert_eq!(output, "ax\0xb\0\0");
+}
+
+
+#[test]
+fn record_separator_should_protect_separator_from_translation() {
+    let output = _tr(vec!["tr", "--record-separator", ";", ";a-z", ",A-Z"], "ab;cd");
+
+    assert_eq!(output, "AB;CD");
+}
+
+
+#[test]
+fn selectors_should_select_records_with_null_data() {
+    let output = _tr(vec!["tr", "-z", "--lines", "2", "a-z", "A-Z"], "a\nb\0c\0d");
+
+    assert_eq!(output, "a\nb\0C\0d");
+
+    let output = _tr(vec!["tr", "--record-separator", ";", "--match", "^c", "a-z", "A-Z"], "ab;cd;ce");
+
+    assert_eq!(output, "ab;CD;CE");
+}
+
+
+/// A writer recording what had been written at each flush.
+#[derive(Default)]
+struct Flushes {
+    written: Vec<u8>,
+    flushed: Vec<String>,
+}
+
+
+impl std::io::Write for Flushes {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.written.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.flushed.push(String::from_utf8(self.written.clone()).unwrap());
+        Ok(())
+    }
+}
+
+
+#[test]
+fn output_should_flush_as_buffering_requires() {
+    let flushes = |args: Vec<&str>| {
+        let mut writer = Flushes::default();
+        tr(args, Cursor::new("ab\ncd\n"), &mut writer, &mut sink()).unwrap();
+        writer.flushed
+    };
+
+    assert_eq!(flushes(vec!["tr", "a", "A"]), vec!["Ab\ncd\n"; 2]);
+    assert_eq!(flushes(vec!["tr", "--line-buffered", "a", "A"]), vec!["Ab\n", "Ab\ncd\n", "Ab\ncd\n", "Ab\ncd\n"]);
+    assert_eq!(flushes(vec!["tr", "-u", "a", "A"]).len(), 8);
+}
+
+
+#[test]
+fn should_not_split_graphemes_across_reads() {
+    let input = std::io::BufReader::with_capacity(1, Cursor::new("ce\u{301}\u{1F600}\n"));
+    let mut output = Vec::new();
+
+    tr(vec!["tr", "e\u{301}\u{1F600}", "xy"], input, &mut output, &mut sink()).unwrap();
+
+    assert_eq!(String::from_utf8(output).unwrap(), "cxy\n");
+}
+
+
+#[cfg(unix)]
+#[test]
+fn bytes_should_translate_raw_bytes_given_in_arguments() {
+    use std::os::unix::ffi::OsStrExt;
+
+    let args = vec![OsStr::new("tr"), OsStr::new("--bytes"), OsStr::from_bytes(b"\xe9\r"), OsStr::new("e")];
+    let mut output = Vec::new();
+
+    tr(args, Cursor::new(&b"caf\xe9\r\n"[..]), &mut output, &mut sink()).unwrap();
+
+    assert_eq!(output, b"cafee\n");
+}
+
+
+#[cfg(unix)]
+#[test]
+fn non_utf8_set_should_be_an_error_without_bytes() {
+    use std::os::unix::ffi::OsStrExt;
+
+    let args = vec![OsStr::new("tr"), OsStr::from_bytes(b"\xe9"), OsStr::new("e")];
+    let result = tr(args, Cursor::new(""), &mut Vec::new(), &mut sink());
+
+    assert_eq!(result.unwrap_err(), "invalid UTF-8 in argument ‘\\xe9’; use --bytes to give bytes");
+}
+
+
+#[test]
+fn posixly_correct_should_stop_options_at_first_operand() {
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_tr"))
+        .args(["a", "-x"])
+        .env("POSIXLY_CORRECT", "1")
+        .stdin(std::process::Stdio::null())
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_tr"))
+        .args(["a", "-x"])
+        .env_remove("POSIXLY_CORRECT")
+        .output()
+        .unwrap();
+
+    assert_eq!(String::from_utf8(output.stderr).unwrap(), "tr: invalid option -- 'x'\n");
+}
+
+
+#[test]
+fn help_should_be_written_to_writer() {
+    let output = _tr(vec!["tr", "--help"], "");
+
+    assert!(output.starts_with("Usage: tr [OPTION]... SET1 [SET2]\n"));
+    assert!(output.contains("  -d, --delete "));
+    assert!(output.contains("  [:alpha:]       all letters\n"));
+    assert!(output.contains("  dashes "));
+}
+
+
+#[test]
+fn version_should_show_unicode_versions() {
+    let output = _tr(vec!["tr", "--vers"], "");
+
+    assert!(output.starts_with("tr[ust] 0.9\n"));
+    assert!(output.contains("Unicode 12.1.0 (grapheme segmentation)"));
+}
+
+
+#[test]
+fn repeat_should_not_appear_in_set1() {
+    let result = tr(vec!["tr", "[a*2]", "x"], Cursor::new("abc"), &mut Vec::new(), &mut sink());
+
+    assert_eq!(result.unwrap_err(),
+               "the [c*] repeat construct may not appear in SET1\n  SET1: [a*2]\n        ^^^^^");
+}
+
+
+#[test]
+fn bsd_should_read_hex_repeat_counts() {
+    let output = _tr(vec!["tr", "--compat=bsd", "a-d", "[x*0x3]y"], "abcd");
+
+    assert_eq!(output, "xxxy");
+}
+
+
+#[test]
+fn bsd_should_read_octal_escapes_above_377() {
+    let output = _tr(vec!["tr", "--compat=bsd", "a", "\\400"], "abc");
+
+    assert_eq!(output, "\u{100}bc");
+
+    let output = _tr(vec!["tr", "a", "\\400"], "abc");
+
+    assert_eq!(output, " bc");
+}
+
+
+#[test]
+fn posix_should_translate_characters_not_graphemes() {
+    let output = _tr(vec!["tr", "--compat=posix", "e", "x"], "e\u{301}");
+
+    assert_eq!(output, "x\u{301}");
+
+    let output = _tr(vec!["tr", "e", "x"], "e\u{301}");
+
+    assert_eq!(output, "e\u{301}");
+}
+
+
+#[test]
+fn posix_should_reject_extensions() {
+    let result = |args: Vec<&str>| tr(args, Cursor::new(""), &mut Vec::new(), &mut sink()).unwrap_err();
+
+    assert_eq!(result(vec!["tr", "--compat=posix", "abc", "x"]),
+               "SET2 is shorter than SET1, which POSIX leaves undefined");
+    assert_eq!(result(vec!["tr", "--compat=posix", "-d", "\\u00e9"]),
+               "invalid escape sequence ‘\\u00e9’\n  SET1: \\u00e9\n        ^^^^^^");
+    assert_eq!(result(vec!["tr", "--compat=posix", "-d", "e\u{301}"]),
+               "‘e\u{301}’ is a grapheme of several characters, which POSIX tr does not support\n  \
+                SET1: e\u{301}\n        ^");
+}
+
+
+#[test]
+fn complement_should_translate_in_order_of_code_point() {
+    let output = _tr(vec!["tr", "-c", "a-z", "xyz"], "\u{0}\u{1}ab\u{2}\u{3}!");
+
+    assert_eq!(output, "xyabzzz");
+
+    let output = _tr(vec!["tr", "-c", "a-z\n", "_"], "hello, world\n");
+
+    assert_eq!(output, "hello__world\n");
+
+    let output = _tr(vec!["tr", "-ct", "a-z", "x"], "\u{0}\u{1}a");
+
+    assert_eq!(output, "x\u{1}a");
+}
+
+
+#[cfg(unix)]
+#[test]
+fn complement_should_fill_set2_to_last_byte() {
+    let mut stdout = Vec::new();
+
+    tr(vec!["tr", "--bytes", "-c", "a", "[x*]y"], Cursor::new(&b"\x01\xfe\xff"[..]), &mut stdout, &mut sink()).unwrap();
+
+    assert_eq!(stdout, b"xxy");
+}
+
+
+#[cfg(unix)]
+#[test]
+fn upper_c_should_translate_complement_in_order_of_collation() {
+    // above \277, × and ÷ collate before the letters, and à before À
+    let run = |option| {
+        let mut stdout = Vec::new();
+
+        tr(vec!["tr", "--bytes", option, "\\0-\\277", "xyzw"], Cursor::new(&b"\xd7\xf7\xe0\xc0"[..]), &mut stdout, &mut sink())
+            .unwrap();
+        stdout
+    };
+
+    assert_eq!(run("-C"), b"xyzw");
+    assert_eq!(run("-c"), b"wwwx");
+}
+
+
+#[test]
+fn ambiguous_octal_escape_should_warn() {
+    let mut errors = Vec::new();
+
+    tr(vec!["tr", "\\400", "xy"], Cursor::new(" 0"), &mut Vec::new(), &mut errors).unwrap();
+
+    assert_eq!(String::from_utf8(errors).unwrap(),
+               "tr: warning: the ambiguous octal escape \\400 is being interpreted as the 2-byte sequence \\040, 0\n");
+}
+
+
+#[test]
+fn stats_should_be_written_to_errors() {
+    let mut stdout = Vec::new();
+    let mut errors = Vec::new();
+
+    tr(vec!["tr", "--stats", "a", "b"], Cursor::new("aa"), &mut stdout, &mut errors).unwrap();
+
+    assert_eq!(stdout, b"bb");
+    assert!(String::from_utf8(errors).unwrap().starts_with("bytes read: 2\n"));
+}
+
+
+#[test]
+fn should_handle_nul_in_sets_and_input() {
+    assert_eq!(_tr(vec!["tr", "\\0", "x"], "a\0b\0"), "axbx");
+    assert_eq!(_tr(vec!["tr", "-d", "\\000"], "a\0b\0"), "ab");
+    assert_eq!(_tr(vec!["tr", "-s", "\\0"], "a\0\0\0b"), "a\0b");
+    assert_eq!(_tr(vec!["tr", "a-c", "\\0\\01\\02"], "abc"), "\0\u{1}\u{2}");
+    assert_eq!(_tr(vec!["tr", "-c", "\\0", "x"], "a\0b"), "x\0x");
+    assert_eq!(_tr(vec!["tr", "--bytes", "\\0a", "xy"], "a\0b"), "yxb");
+}
+
+
+#[test]
+fn should_canonicalize_set() {
+    let output = _tr(vec!["tr", "--canonicalize", "abcdefgh0123456789ABCDEF\\001\\002\\003\\004-"], "");
+
+    assert_eq!(output, "a-h0-9A-F\\001-\\004-\n");
+
+    let mut stdout = Vec::new();
+
+    tr(vec!["tr", "--bytes", "--canonicalize", "\\351\\0"], Cursor::new(""), &mut stdout, &mut sink()).unwrap();
+
+    assert_eq!(stdout, b"\xe9\\000\n");
+}
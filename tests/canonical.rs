@@ -0,0 +1,90 @@
+use rstest::rstest;
+
+use tr::canonical::canonicalize;
+use tr::dialect::Dialect;
+use tr::parser::Syntax;
+
+
+#[rstest(
+    case => [("abcdefghijklmnopqrstuvwxyz", "a-z"),
+             ("abc", "abc"),
+             ("ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz", "[:alpha:]"),
+             ("0-9A-Za-z_", "[:alnum:]_"),
+             ("0-9A-Fa-f", "[:xdigit:]"),
+             ("0-9", "0-9"),
+             ("a-z", "a-z"),
+             (" -~", " -~"),
+             ("\\0-\\37\\177", "[:cntrl:]"),
+             ("\\001\\002\\003\\004", "\\001-\\004"),
+             ("\\t ", "[:blank:]"),
+             ("a\\-z", "a\\-z"),
+             ("\\-a", "-a"),
+             ("a-", "a-"),
+             ("-", "-"),
+             ("--", "--"),
+             ("-+-", "-+-"),
+             ("*+,-", "*--"),
+             ("x\\\\[", "x\\\\["),
+             ("[", "["),
+             ("a[b", "a[b"),
+             ("\\[:", "\\[:"),
+             ("\\[=", "\\[="),
+             ("\\[x\\*", "\\[x*"),
+             ("\\[\\[\\*]", "\\[[*]"),
+             ("\\u{200b}\\u{301}", "\\u{200b}\\u{301}"),
+             ("[x*3]y", "xxxy"),
+             ("a[.*]yz", "a[.*]yz")]
+)]
+fn should_print_shortest_set(case: (&str, &str)) {
+    let (set, expected) = case;
+
+    assert_eq!(canonicalize(set, Syntax::default()).unwrap(), expected);
+}
+
+
+#[rstest(
+    set => ["a-z", "zyxa-c", "aa-c", "\\n-\\r", "-a-", "[:punct:]", "[:graph:]\\t",
+            "\\p{Nd}", "\\p{Greek}", "\\u{300}-\\u{36f}", "👍🏽🇫🇷é", "x\\400[y*4]",
+            "\\[[:alpha:]\\[", "\\[\\[\\*]"]
+)]
+fn canonical_set_should_expand_alike(set: &str) {
+    let syntax = Syntax::default();
+    let canonical = canonicalize(set, syntax).unwrap();
+
+    assert_eq!(syntax.split_set2(&canonical).unwrap(), syntax.split_set2(set).unwrap());
+}
+
+
+#[test]
+fn canonical_set_should_escape_operators_in_extended_syntax() {
+    let syntax = Syntax { extended: true, ..Syntax::default() };
+    let canonical = canonicalize("[&\\[\\]]--[x]", syntax).unwrap();
+
+    assert_eq!(canonical, "\\&\\[\\]");
+    assert_eq!(syntax.expand(&canonical).unwrap(), "&[]");
+}
+
+
+#[test]
+fn posix_canonical_set_should_not_use_unicode_escapes() {
+    let syntax = Syntax { dialect: Dialect::Posix, ..Syntax::default() };
+
+    assert_eq!(canonicalize("\\n\\u{7f}", Syntax::default()).unwrap(), "\\n\\177");
+    assert_eq!(canonicalize("ab", syntax).unwrap(), "ab");
+}
+
+
+#[test]
+fn invalid_set_should_produce_error() {
+    assert!(canonicalize("[:alfa:]", Syntax::default()).is_err());
+    assert!(canonicalize("z-a", Syntax::default()).is_err());
+}
+
+
+#[test]
+fn invalid_set_error_should_name_set() {
+    let error = canonicalize("z-a", Syntax::default()).unwrap_err();
+
+    assert!(error.contains("SET: z-a"));
+    assert!(!error.contains("SET2"));
+}
@@ -0,0 +1,29 @@
+use tr::complement::{collation_key, mapping, Order};
+use tr::parser::CharSet;
+
+
+/// The complement of `set1` in the BMP in order of collation, sorting
+/// every character.
+fn _sorted(set1: &str) -> Vec<char> {
+    let mut chars: Vec<char> = ('\0'..='\u{ffff}').filter(|&c| !set1.contains(c)).collect();
+    chars.sort_by_key(|&c| collation_key(c));
+    chars
+}
+
+
+#[test]
+fn collation_ends_should_match_full_sort_for_non_latin_set1() {
+    let set1 = "αβγδεζηθικλμνξοπρστυφχψωάέήίόύώ";
+    let sorted = _sorted(set1);
+
+    // few enough to be among the controls, and too many to be
+    for n in &[3, 100] {
+        let before = "x".repeat(*n);
+        let (pairs, rest) = mapping(&CharSet::from(set1), &before, None, "yz", Order::Collation, '\u{ffff}', false);
+        let chars: Vec<char> = pairs.iter().map(|(c, _)| *c).collect();
+
+        assert_eq!(chars[..*n], sorted[..*n]);
+        assert_eq!(chars[*n..], sorted[sorted.len() - 2..]);
+        assert_eq!(rest.as_deref(), Some("x"));
+    }
+}
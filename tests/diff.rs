@@ -0,0 +1,66 @@
+use tr::command::Op;
+use tr::diff::{diff, Style};
+use tr::stats::{count, Counter, Effect};
+
+
+fn _preview(op: Op, counter: &Counter, style: Style, input: &[&str]) -> String {
+    let mut op = diff(op, counter, style);
+
+    input.iter().chain(&[""]).filter_map(|g| op(g)).collect()
+}
+
+
+fn _delete(target: &'static str) -> Op {
+    Box::new(move |b| match b == target {
+        true => None,
+        false => Some(b.to_owned())
+    })
+}
+
+
+#[test]
+fn should_report_only_changed_lines() {
+    let counter = Counter::default();
+    let op = count(_delete("x"), &counter, Effect::Delete);
+
+    let output = _preview(op, &counter, Style::Plain, &["a", "\n", "x", "b", "\n", "c"]);
+
+    assert_eq!(output, "2: [-x-]b\n");
+}
+
+
+#[test]
+fn should_flush_changed_final_line_without_newline() {
+    let counter = Counter::default();
+    let op = count(_delete("x"), &counter, Effect::Delete);
+
+    let output = _preview(op, &counter, Style::Plain, &["a", "\n", "b", "x"]);
+
+    assert_eq!(output, "2: b[-x-]\n");
+}
+
+
+#[test]
+fn should_mark_replacements_and_squeezes() {
+    let counter = Counter::default();
+    let translate: Op = Box::new(|b| Some(if b == "\t" { " ".to_owned() } else { b.to_owned() }));
+    let squeeze: Op = Box::new(|b| if b == "-" { None } else { Some(b.to_owned()) });
+    let mut translate = count(translate, &counter, Effect::Translate);
+    let mut squeeze = count(squeeze, &counter, Effect::Squeeze);
+    let op: Op = Box::new(move |b| translate(b).and_then(|c| squeeze(&c)));
+
+    let output = _preview(op, &counter, Style::Plain, &["a", "\t", "-", "\n"]);
+
+    assert_eq!(output, "1: a[-\\t-]{+ +}[~-~]\n");
+}
+
+
+#[test]
+fn color_style_should_use_ansi_escapes() {
+    let counter = Counter::default();
+    let op = count(_delete("x"), &counter, Effect::Delete);
+
+    let output = _preview(op, &counter, Style::Color, &["x", "\n"]);
+
+    assert_eq!(output, "1: \x1b[9;31mx\x1b[0m\n");
+}
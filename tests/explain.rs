@@ -0,0 +1,88 @@
+use std::io::{sink, Cursor};
+
+use tr::arg_parser::parse_args;
+use tr::command::tr;
+use tr::explain::explain;
+
+
+fn _explain(args: &[&str]) -> String {
+    let config = parse_args(args).unwrap();
+    let mut output = Vec::new();
+
+    explain(&config, &mut output).unwrap();
+
+    String::from_utf8(output).unwrap()
+}
+
+
+#[test]
+fn should_show_tokens_and_expanded_sets() {
+    let output = _explain(&["tr", "a-c\\n", "xyz_"]);
+
+    assert!(output.contains("  tokens: CharRange ‘a-c’, Literal ‘\\n’\n"));
+    assert!(output.contains("  graphemes: (4) ‘a’ U+0061, ‘b’ U+0062, ‘c’ U+0063, ‘\\n’ U+000A\n"));
+}
+
+
+#[test]
+fn should_show_mapping_with_code_points() {
+    let output = _explain(&["tr", "\\N{NO-BREAK SPACE}é", " e"]);
+
+    assert!(output.contains("  ‘\\u{a0}’ U+00A0 → ‘ ’ U+0020\n"));
+    assert!(output.contains("  ‘é’ U+00E9 → ‘e’ U+0065\n"));
+}
+
+
+#[test]
+fn should_warn_of_duplicates_and_padding() {
+    let output = _explain(&["tr", "abca", "xy"]);
+
+    assert!(output.contains("warning: ‘a’ appears 2 times in SET1; the last mapping applies\n"));
+    assert!(output.contains("warning: SET2 padded from 2 to 4 graphemes by repeating ‘y’\n"));
+}
+
+
+#[test]
+fn should_warn_of_truncation() {
+    let output = _explain(&["tr", "-t", "abc", "x"]);
+
+    assert!(output.contains("warning: SET1 truncated from 3 to 1 graphemes\n"));
+}
+
+
+#[test]
+fn should_show_complement_mapping() {
+    let output = _explain(&["tr", "-c", "a-z", "0-2"]);
+
+    assert!(output.contains("mapping:\n  ‘\\u{0}’ U+0000 → ‘0’ U+0030\n  ‘\\u{1}’ U+0001 → ‘1’ U+0031\n"));
+    assert!(output.contains("  every other grapheme not in SET1 → ‘2’ U+0032\n"));
+    assert!(!output.contains("‘a’ U+0061 →"));
+
+    let output = _explain(&["tr", "-ct", "a-z", "[_*]x"]);
+
+    assert!(output.contains("  ‘\\u{10ffff}’ U+10FFFF → ‘x’ U+0078\n"));
+    assert!(output.contains("  every other grapheme not in SET1 → ‘_’ U+005F\n"));
+
+    let output = _explain(&["tr", "-ct", "a-z", "0"]);
+
+    assert!(output.contains("  every other grapheme not in SET1 → (unchanged)\n"));
+}
+
+
+#[test]
+fn should_show_membership_for_delete_and_squeeze() {
+    let output = _explain(&["tr", "-ds", "ab", "c"]);
+
+    assert!(output.contains("deleted: (2) ‘a’ U+0061, ‘b’ U+0062\n"));
+    assert!(output.contains("squeezed: (1) ‘c’ U+0063\n"));
+}
+
+
+#[test]
+fn explain_should_not_read_input() {
+    let mut output = Vec::new();
+
+    tr(&["tr", "--explain", "a", "b"], Cursor::new("aaa"), &mut output, &mut sink()).unwrap();
+
+    assert!(!String::from_utf8(output).unwrap().contains("bbb"));
+}
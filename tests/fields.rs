@@ -0,0 +1,29 @@
+use rstest::rstest;
+
+use tr::fields::Fields;
+
+
+#[rstest(
+    case => [("1", vec![1], vec![2]), ("2,4", vec![2, 4], vec![1, 3, 5]),
+             ("2-3", vec![2, 3], vec![1, 4]), ("3-", vec![3, 99], vec![2]),
+             ("-2", vec![1, 2], vec![3])]
+)]
+fn field_list_should_select_fields(case: (&str, Vec<usize>, Vec<usize>)) {
+    let (list, selected, unselected) = case;
+    let fields = Fields::parse(list).unwrap();
+
+    assert!(selected.iter().all(|&n| fields.contains(n)));
+    assert!(unselected.iter().all(|&n| !fields.contains(n)));
+}
+
+
+#[rstest(
+    case => [("0", "fields are numbered from 1"), ("x", "invalid field value ‘x’"),
+             ("1,,2", "invalid field range ‘’"), ("-", "invalid field range ‘-’"),
+             ("3-2", "invalid decreasing range")]
+)]
+fn malformed_field_list_should_produce_error(case: (&str, &str)) {
+    let (list, expected) = case;
+
+    assert_eq!(Fields::parse(list).unwrap_err(), expected);
+}
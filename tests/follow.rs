@@ -0,0 +1,150 @@
+use std::fs::{self, OpenOptions};
+use std::io::{sink, Cursor, ErrorKind, Read, Write};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use tr::command::tr;
+use tr::follow::Follow;
+
+
+fn _path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("tr-follow-{}-{}", std::process::id(), name))
+}
+
+
+fn _append(path: &PathBuf, text: &str) {
+    OpenOptions::new().append(true).create(true).open(path).unwrap()
+        .write_all(text.as_bytes()).unwrap();
+}
+
+
+fn _read(follow: &mut Follow) -> String {
+    let mut buffer = [0; 64];
+    let length = follow.read(&mut buffer).unwrap();
+
+    String::from_utf8(buffer[..length].to_vec()).unwrap()
+}
+
+
+#[test]
+fn follow_should_read_appended_data() {
+    let path = _path("append");
+    _append(&path, "one\n");
+
+    let mut follow = Follow::open(&path).unwrap();
+    assert_eq!(_read(&mut follow), "one\n");
+
+    _append(&path, "two\n");
+    assert_eq!(_read(&mut follow), "two\n");
+
+    fs::remove_file(&path).unwrap();
+}
+
+
+#[test]
+fn follow_should_start_over_after_truncation() {
+    let path = _path("truncate");
+    _append(&path, "a long first line\n");
+
+    let mut follow = Follow::open(&path).unwrap();
+    assert_eq!(_read(&mut follow), "a long first line\n");
+
+    fs::write(&path, "new\n").unwrap();
+    assert_eq!(_read(&mut follow), "new\n");
+
+    fs::remove_file(&path).unwrap();
+}
+
+
+#[cfg(unix)]
+#[test]
+fn follow_should_read_replacement_file() {
+    let path = _path("rotate");
+    let rotated = _path("rotate.1");
+    _append(&path, "old\n");
+
+    let mut follow = Follow::open(&path).unwrap();
+    assert_eq!(_read(&mut follow), "old\n");
+
+    fs::rename(&path, &rotated).unwrap();
+    _append(&path, "replacement\n");
+    assert_eq!(_read(&mut follow), "replacement\n");
+
+    fs::remove_file(&path).unwrap();
+    fs::remove_file(&rotated).unwrap();
+}
+
+
+#[test]
+fn follow_should_report_no_new_data_once() {
+    let path = _path("idle");
+    _append(&path, "partial");
+
+    let mut follow = Follow::open(&path).unwrap();
+    assert_eq!(_read(&mut follow), "partial");
+    assert_eq!(follow.read(&mut [0; 64]).unwrap_err().kind(), ErrorKind::WouldBlock);
+
+    _append(&path, " line\n");
+    assert_eq!(_read(&mut follow), " line\n");
+
+    fs::remove_file(&path).unwrap();
+}
+
+
+/// A writer whose output can be looked at while another thread writes.
+#[derive(Clone,Default)]
+struct Shared(Arc<Mutex<Vec<u8>>>);
+
+
+impl Write for Shared {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+
+/// Follow a file holding `text` with `args` added, returning the output
+/// once it is `expected`, or after two seconds.
+fn _follow(name: &str, args: &[&str], text: &str, expected: &str) -> String {
+    let path = _path(name);
+    _append(&path, text);
+
+    let output = Shared::default();
+    let mut writer = output.clone();
+    let mut args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+    args.splice(1..1, vec!["--follow".to_owned(), path.to_str().unwrap().to_owned()]);
+
+    // tr follows the file forever, so it is left running
+    thread::spawn(move || tr(&args, Cursor::new(""), &mut writer, &mut sink()));
+
+    for _ in 0..40 {
+        if output.0.lock().unwrap().as_slice() == expected.as_bytes() {
+            break;
+        }
+
+        thread::sleep(Duration::from_millis(50));
+    }
+
+    fs::remove_file(&path).unwrap();
+
+    let output = output.0.lock().unwrap().clone();
+    String::from_utf8(output).unwrap()
+}
+
+
+#[test]
+fn follow_should_write_last_line_without_newline() {
+    assert_eq!(_follow("last-line", &["tr", "a-z", "A-Z"], "one\ntwo", "ONE\nTWO"), "ONE\nTWO");
+}
+
+
+#[test]
+fn follow_should_write_last_line_selected_by_number() {
+    assert_eq!(_follow("lines", &["tr", "--lines", "2", "a-z", "A-Z"], "one\ntwo", "one\nTWO"), "one\nTWO");
+}
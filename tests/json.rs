@@ -0,0 +1,75 @@
+use rstest::rstest;
+
+use tr::arg_parser::parse_args;
+use tr::command::Op;
+use tr::json::json;
+
+
+fn _json(args: &[&str], input: &str) -> String {
+    let config = parse_args(args).unwrap();
+    let op: Op = match config.delete {
+        true => tr::command::delete(&config).unwrap(),
+        false => tr::command::translate(&config).unwrap()
+    };
+    let mut op = json(op, &config);
+
+    input.chars().filter_map(|c| op(&c.to_string())).collect()
+}
+
+
+#[rstest(
+    case => [(r#"{"a": "a"}"#, r#"{"a": "A"}"#),
+             (r#"["a", {"b": ["c"]}, "d"]"#, r#"["A", {"b": ["C"]}, "D"]"#),
+             (r#"{"a": {"b": "b"}, "c": "c"}"#, r#"{"a": {"b": "B"}, "c": "C"}"#),
+             (r#""abc""#, r#""ABC""#),
+             (r#"{"n": 1, "t": true}"#, r#"{"n": 1, "t": true}"#)]
+)]
+fn json_should_translate_values_only(case: (&str, &str)) {
+    let (input, expected) = case;
+
+    assert_eq!(_json(&["tr", "--json", "a-z", "A-Z"], input), expected);
+}
+
+
+#[test]
+fn json_keys_should_translate_keys_only() {
+    let input = r#"{"a": "a", "b": {"c": ["d"]}}"#;
+
+    assert_eq!(_json(&["tr", "--json-keys", "a-z", "A-Z"], input), r#"{"A": "a", "B": {"C": ["d"]}}"#);
+    assert_eq!(_json(&["tr", "--json", "--json-keys", "a-z", "A-Z"], input), r#"{"A": "A", "B": {"C": ["D"]}}"#);
+}
+
+
+#[rstest(
+    case => [(r#"["a\"b"]"#, r#"["a\"b"]"#), (r#"["a\\b"]"#, r#"["a\\b"]"#),
+             (r#"["\u00e9\u0041"]"#, r#"["\u00e9\u0041"]"#)]
+)]
+fn json_should_keep_escapes_the_op_leaves_alone(case: (&str, &str)) {
+    let (input, expected) = case;
+
+    assert_eq!(_json(&["tr", "--json", "x", "y"], input), expected);
+}
+
+
+#[test]
+fn json_should_apply_op_to_escaped_characters() {
+    assert_eq!(_json(&["tr", "--json", "é", "e"], r#"["caf\u00e9"]"#), r#"["cafe"]"#);
+    assert_eq!(_json(&["tr", "--json", "-d", "\n"], r#"["a\nb"]"#), r#"["ab"]"#);
+    assert_eq!(_json(&["tr", "--json", "😀", "x"], r#"["\uD83D\uDE00"]"#), r#"["x"]"#);
+}
+
+
+#[test]
+fn json_should_escape_output() {
+    assert_eq!(_json(&["tr", "--json", "'", "\""], r#"["it's"]"#), r#"["it\"s"]"#);
+    assert_eq!(_json(&["tr", "--json", "/", "\\\\"], r#"["a/b"]"#), r#"["a\\b"]"#);
+    assert_eq!(_json(&["tr", "--json", " ", "\\n"], r#"["a b"]"#), r#"["a\nb"]"#);
+}
+
+
+#[test]
+fn json_should_never_alter_structure() {
+    let input = r#"{"q": "\"", "b": "\\", "l": [1, 2]}"#;
+
+    assert_eq!(_json(&["tr", "--json", "-d", "\"\\\\[]{},:"], input), r#"{"q": "", "b": "", "l": [1, 2]}"#);
+}
@@ -1,6 +1,9 @@
+use std::borrow::Cow;
+
 use rstest::rstest;
 
-use tr::lex::tokenize;
+use tr::dialect::Dialect;
+use tr::lex::{tokenize, tokenize_extended};
 use tr::lex::TokenType::{*};
 
 
@@ -76,7 +79,7 @@ fn string_literal_should_end_token_stream(s: &str) {
 
 // FIXME: tr actual treats pattern "2[.*a]3" as an error
 #[rstest(
-    s => ["a[::]z", "0[:abcd", "-", "-q", "a[:xdigi:]z", "0[xx*]9",
+    s => ["a[::]z", "0[:abcd", "-", "-q", "0[xx*]9",
           "Z[:alnum]A", "A-"]
 )]
 fn pseudo_repeats_and_classes_should_be_treated_as_literals(s: &str) {
@@ -146,8 +149,8 @@ fn should_tokenize_octal_escape(case: (&str, &str), prefix: &str, suffix: &str)
 fn complicated_octal_parsing_scenario() {
     let s = "\\0asdf[:xdigi:]jkl\\01\\012\\0123\\9\\09\\019[::]X-";
 
-    let result = tokenize(s).map(|t| t.token).collect::<Vec<String>>();
-    let expected = vec!["\u{0}", "asdf[:xdigi:]jkl", "\u{1}", "\n", "\n",
+    let result = tokenize(s).map(|t| t.token.into_owned()).collect::<Vec<String>>();
+    let expected = vec!["\u{0}", "asdf", "[:xdigi:]", "jkl", "\u{1}", "\n", "\n",
                         "3", "9", "\u{0}", "9", "\u{1}", "9[::]X-"];
 
     assert_eq!(result, expected);
@@ -371,3 +374,278 @@ fn unicode_sequence_should_parse() {
     assert_eq!(token.token_type, Literal);
     assert_eq!(token.token, s);
 }
+
+
+#[rstest(
+    case => [(r"\x41", "A"), (r"\x7e", "~"), (r"\xA0", "\u{a0}"), (r"\x9", "\t"),
+             (r"\u00A0", "\u{a0}"), (r"\u200D", "\u{200d}"), (r"\u{1F600}", "😀"),
+             (r"\u{0}", "\u{0}"), (r"\N{NO-BREAK SPACE}", "\u{a0}"),
+             (r"\N{zero width joiner}", "\u{200d}")]
+)]
+fn should_tokenize_hex_unicode_and_named_escapes(case: (&str, &str)) {
+    let (s, expected) = case;
+    let mut tokens = tokenize(s);
+    let token = tokens.next().unwrap();
+
+    assert_eq!(token.token, expected);
+    assert_eq!(token.token_type, Literal);
+    assert_eq!(tokens.next(), None);
+}
+
+
+#[test]
+fn hex_escape_should_take_at_most_two_digits() {
+    let result = tokenize(r"\x414").map(|t| t.token.into_owned()).collect::<Vec<String>>();
+
+    assert_eq!(result, vec!["A", "4"]);
+}
+
+
+#[test]
+fn short_unicode_escape_should_take_exactly_four_digits() {
+    let result = tokenize(r"\u00e9f").map(|t| t.token.into_owned()).collect::<Vec<String>>();
+
+    assert_eq!(result, vec!["é", "f"]);
+}
+
+
+#[rstest(
+    case => [(r"\xg", vec!["x", "g"]), (r"\u12", vec!["u", "12"]),
+             (r"\u{12", vec!["u", "{12"]), (r"\u{}", vec!["u", "{}"]),
+             (r"\N", vec!["N"]), (r"\N{SPACE", vec!["N", "{SPACE"])]
+)]
+fn incomplete_escape_should_take_letter_literally(case: (&str, Vec<&str>)) {
+    let (s, expected) = case;
+    let result = tokenize(s).map(|t| t.token.into_owned()).collect::<Vec<String>>();
+
+    assert_eq!(result, expected);
+}
+
+
+#[rstest(
+    s => [r"\N{NO SUCH CHARACTER}", r"\u{110000}", r"\uD800", r"\u{dfff}"]
+)]
+fn malformed_escape_should_be_invalid(s: &str) {
+    let token = tokenize(s).next().unwrap();
+
+    assert_eq!(token.token, s);
+    assert_eq!(token.token_type, Invalid);
+}
+
+
+#[rstest(
+    case => [(r"\u{400}-\u{4FF}", "\u{400}-\u{4ff}"), (r"\000-\037", "\u{0}-\u{1f}"),
+             (r"\x41-Z", "A-Z"), (r"a-\x7a", "a-z"), (r"\t-\r", "\t-\r"),
+             (r"é-\N{LATIN SMALL LETTER U WITH DIAERESIS}", "é-ü")]
+)]
+fn escapes_should_serve_as_range_endpoints(case: (&str, &str)) {
+    let (s, expected) = case;
+    let mut tokens = tokenize(s);
+    let token = tokens.next().unwrap();
+
+    assert_eq!(token.token, expected);
+    assert_eq!(token.token_type, CharRange);
+    assert_eq!(tokens.next(), None);
+}
+
+
+#[test]
+fn escape_followed_by_trailing_dash_should_not_be_range() {
+    let result = tokenize(r"\n-").map(|t| t.token.into_owned()).collect::<Vec<String>>();
+
+    assert_eq!(result, vec!["\n", "-"]);
+}
+
+
+#[rstest(
+    s => ["[:script=Greek:]", "[:sc=Cyrl:]", "[:gc=Lu:]", "[:general_category=N:]",
+          "[:emoji:]", r"\p{Nd}", r"\p{Greek}", r"\p{script=Latin}"]
+)]
+fn unicode_property_should_be_class(s: &str) {
+    let token = tokenize(s).next().unwrap();
+
+    assert_eq!(token.token, s);
+    assert_eq!(token.token_type, CharClass);
+}
+
+
+#[test]
+fn unknown_property_escape_should_be_invalid() {
+    let token = tokenize(r"\p{Klingon}").next().unwrap();
+
+    assert_eq!(token.token_type, Invalid);
+}
+
+
+#[rstest(
+    case => [("[:alpha:]--[aeiou]", vec!["[:alpha:]", "--", "[", "aeiou", "]"]),
+             ("[:punct:]&&[!-/]", vec!["[:punct:]", "&&", "[", "!-/", "]"]),
+             ("[_-]", vec!["[", "_-", "]"]),
+             ("a-z--[b]", vec!["a-z", "--", "[", "b", "]"]),
+             ("]a[", vec!["]a", "["])]
+)]
+fn extended_syntax_should_split_operators_and_nested_sets(case: (&str, Vec<&str>)) {
+    let (s, expected) = case;
+    let result = tokenize_extended(s).map(|t| t.token.into_owned()).collect::<Vec<String>>();
+
+    assert_eq!(result, expected);
+}
+
+
+#[test]
+fn operators_should_be_literal_outside_extended_syntax() {
+    let result = tokenize("[:alpha:]--[aeiou]").map(|t| t.token.into_owned()).collect::<Vec<String>>();
+
+    assert_eq!(result, vec!["[:alpha:]", "--[", "aeiou]"]);
+}
+
+
+#[rstest(
+    case => [(Dialect::Gnu, vec![" ", "0"]),
+             (Dialect::Bsd, vec!["\u{100}"]),
+             (Dialect::Posix, vec!["\\400"])]
+)]
+fn octal_escape_above_377_should_depend_on_dialect(case: (Dialect, Vec<&str>)) {
+    let (dialect, expected) = case;
+    let result = tokenize(r"\400").dialect(dialect).map(|t| t.token.into_owned()).collect::<Vec<String>>();
+
+    assert_eq!(result, expected);
+}
+
+
+#[rstest(
+    s => [r"\x41", r"\u0041", r"\u{41}", r"\N{LATIN CAPITAL LETTER A}", r"\p{Lu}"]
+)]
+fn posix_should_not_have_extended_escapes(s: &str) {
+    let token = tokenize(s).dialect(Dialect::Posix).next().unwrap();
+
+    assert_eq!(token.token_type, Invalid);
+    assert_eq!(token.token, s);
+}
+
+
+#[test]
+fn posix_should_not_have_property_classes() {
+    let token = tokenize("[:emoji:]").dialect(Dialect::Posix).next().unwrap();
+
+    assert_eq!(token.token_type, Invalid);
+}
+
+
+#[test]
+fn bsd_repeat_count_may_be_hex() {
+    let token = tokenize("[x*0x1f]").dialect(Dialect::Bsd).next().unwrap();
+
+    assert_eq!(token.token_type, CharRepeat);
+    assert_eq!(tokenize("[x*0x1f]").next().unwrap().token_type, Literal);
+}
+
+
+#[rstest(
+    s => ["[:xdigi:]", "[:alfa:]", "[:script=Klingon:]", "[:foo bar:]"]
+)]
+fn unknown_class_should_be_invalid(s: &str) {
+    let set = format!("a{}z", s);
+    let tokens: Vec<_> = tokenize(&set).collect();
+
+    assert_eq!(tokens[1].token_type, Invalid);
+    assert_eq!(tokens[1].token, s);
+}
+
+
+#[rstest(
+    case => [("abc", vec![(0, 3, 0, 3)]),
+             ("a-z[:alpha:]", vec![(0, 3, 0, 3), (3, 12, 3, 12)]),
+             ("\\n\\u{e9}-\\u{ff}x", vec![(0, 2, 0, 2), (2, 15, 2, 15), (15, 16, 15, 16)]),
+             ("é[x*2]ü", vec![(0, 2, 0, 1), (2, 7, 1, 6), (7, 9, 6, 7)]),
+             ("a-\\p{Nd}", vec![(0, 2, 0, 2), (2, 8, 2, 8)])]
+)]
+fn tokens_should_have_spans(case: (&str, Vec<(usize, usize, usize, usize)>)) {
+    let (s, expected) = case;
+    let spans: Vec<_> = tokenize(s)
+        .map(|t| (t.span.start, t.span.end, t.span.column, t.span.end_column))
+        .collect();
+
+    assert_eq!(spans, expected);
+}
+
+
+#[test]
+fn extended_tokens_should_have_spans() {
+    let spans: Vec<_> = tokenize_extended("[a]--b").map(|t| (t.span.start, t.span.end)).collect();
+
+    assert_eq!(spans, vec![(0, 1), (1, 2), (2, 3), (3, 5), (5, 6)]);
+}
+
+
+#[rstest(
+    s => ["abc", "a-z", "[:alpha:]", "[x*3]", "[=e=]", "\\p{Nd}"]
+)]
+fn tokens_should_borrow_from_set(s: &str) {
+    for token in tokenize(s) {
+        assert!(matches!(token.token, Cow::Borrowed(_)), "{:?}", token);
+    }
+}
+
+
+#[rstest(
+    s => ["\\u{e9}", "\\n-z", "\\101"]
+)]
+fn escapes_should_be_owned(s: &str) {
+    assert!(matches!(tokenize(s).next().unwrap().token, Cow::Owned(_)));
+}
+
+
+#[rstest(
+    s => ["a-", "[:", "[x*1", "\\N{", "[", "-[a"]
+)]
+fn long_sets_should_not_overflow(s: &str) {
+    let set = s.repeat(50_000);
+
+    assert!(tokenize(&set).count() > 0);
+    assert!(tokenize_extended(&set).count() > 0);
+}
+
+
+#[rstest(
+    case => [(r"\400", vec![r"\400"]),
+             (r"a-\777z", vec![r"\777"]),
+             (r"\500-\600", vec![r"\500", r"\600"]),
+             (r"\377\0400\\400\4", vec![])]
+)]
+fn ambiguous_octal_escapes_should_warn(case: (&str, Vec<&str>)) {
+    let (s, escapes) = case;
+    let mut tokens = tokenize(s);
+    tokens.by_ref().for_each(drop);
+
+    let warned: Vec<_> = tokens.warnings().iter()
+        .map(|w| w.split(' ').nth(4).unwrap())
+        .collect();
+
+    assert_eq!(warned, escapes);
+}
+
+
+#[test]
+fn octal_escapes_should_not_warn_in_bsd() {
+    let mut tokens = tokenize(r"\400").dialect(Dialect::Bsd);
+    tokens.by_ref().for_each(drop);
+
+    assert!(tokens.warnings().is_empty());
+}
+
+
+#[rstest(
+    case => [(r"\0", vec!["\0"]),
+             (r"\0a", vec!["\0", "a"]),
+             (r"\08", vec!["\0", "8"]),
+             (r"\0\0", vec!["\0", "\0"]),
+             (r"\000x", vec!["\0", "x"]),
+             (r"\0-\10", vec!["\0-\u{8}"])]
+)]
+fn nul_escapes_should_end_at_first_non_octal(case: (&str, Vec<&str>)) {
+    let (s, expected) = case;
+    let result = tokenize(s).map(|t| t.token.into_owned()).collect::<Vec<String>>();
+
+    assert_eq!(result, expected);
+}
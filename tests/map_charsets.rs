@@ -1,17 +1,17 @@
-use tr::parser::map_charsets;
+use tr::parser::{map_charsets, map_targets};
 
 use rstest::rstest;
 
 
 #[test]
 fn mapping_empty_charsets_should_succeed() {
-    map_charsets("", "");
+    map_charsets("", "").unwrap();
 }
 
 
 #[test]
 fn should_map_unit_length_set1_to_set2() {
-    let map = map_charsets("a", "z");
+    let map = map_charsets("a", "z").unwrap();
 
     assert_eq!(&"z", map.get("a").unwrap());
 }
@@ -19,7 +19,7 @@ fn should_map_unit_length_set1_to_set2() {
 
 #[test]
 fn should_map_same_length_set1_to_set2() {
-    let map = map_charsets("abcde", "zyxwv");
+    let map = map_charsets("abcde", "zyxwv").unwrap();
 
     assert_eq!(&"z", map.get("a").unwrap());
     assert_eq!(&"y", map.get("b").unwrap());
@@ -31,7 +31,7 @@ fn should_map_same_length_set1_to_set2() {
 
 #[test]
 fn extraneous_chars_in_set2_should_be_ignored() {
-    let map = map_charsets("a", "zEXTRA!EXTRA!");
+    let map = map_charsets("a", "zEXTRA!EXTRA!").unwrap();
 
     assert_eq!(&"z", map.get("a").unwrap());
 }
@@ -39,7 +39,7 @@ fn extraneous_chars_in_set2_should_be_ignored() {
 
 #[test]
 fn should_map_unicode_to_unicode() {
-    let map = map_charsets("é", "É");
+    let map = map_charsets("é", "É").unwrap();
 
     assert_eq!(&"É", map.get("é").unwrap());
 }
@@ -47,7 +47,7 @@ fn should_map_unicode_to_unicode() {
 
 #[test]
 fn should_map_unicode_to_ascii() {
-    let map = map_charsets("é", "#");
+    let map = map_charsets("é", "#").unwrap();
 
     assert_eq!(&"#", map.get("é").unwrap());
 }
@@ -55,7 +55,7 @@ fn should_map_unicode_to_ascii() {
 
 #[test]
 fn should_map_ascii_to_unicode() {
-    let map = map_charsets("*", "É");
+    let map = map_charsets("*", "É").unwrap();
 
     assert_eq!(&"É", map.get("*").unwrap());
 }
@@ -67,16 +67,15 @@ fn should_map_ascii_to_unicode() {
 )]
 fn unicode_in_mapping_should_preserve_character_length(case: (&str, &str)) {
     let (source, target) = case;
-    let map = map_charsets("éasdf♥!1", "Éjkl;%¡0");
+    let map = map_charsets("éasdf♥!1", "Éjkl;%¡0").unwrap();
 
     assert_eq!(&target, map.get(source).unwrap());
 }
 
 
 #[test]
-#[ignore]
 fn should_extend_set2_to_set1_length() {
-    let map = map_charsets("1234567890", ".");
+    let map = map_charsets("1234567890", ".").unwrap();
 
     assert_eq!(&".", map.get("2").unwrap());
     assert_eq!(&".", map.get("0").unwrap());
@@ -85,7 +84,7 @@ fn should_extend_set2_to_set1_length() {
 
 #[test]
 fn should_map_escape_in_set1() {
-    let map = map_charsets(r"\a", "@");
+    let map = map_charsets(r"\a", "@").unwrap();
 
     assert_eq!(&"@", map.get("\u{07}").unwrap());
 }
@@ -93,7 +92,7 @@ fn should_map_escape_in_set1() {
 
 #[test]
 fn should_map_escapes_in_set1() {
-    let map = map_charsets(r"\n\t\v\b\r", "01234");
+    let map = map_charsets(r"\n\t\v\b\r", "01234").unwrap();
 
     assert_eq!(&"0", map.get("\n").unwrap());
     assert_eq!(&"1", map.get("\t").unwrap());
@@ -105,13 +104,8 @@ fn should_map_escapes_in_set1() {
 
 #[test]
 fn should_map_escapes_in_mixed_set1() {
-    let map = map_charsets(r" \n\t+/|", "· t_=-");
+    let map = map_charsets(r" \n\t+/|", "· t_=-").unwrap();
 
-    println!("");
-    println!("");
-    println!("{:?}", map);
-    println!("");
-    println!("");
     assert_eq!(&"·", map.get(" ").unwrap());
     assert_eq!(&" ", map.get("\n").unwrap());
     assert_eq!(&"t", map.get("\t").unwrap());
@@ -123,7 +117,7 @@ fn should_map_escapes_in_mixed_set1() {
 
 #[test]
 fn should_map_escape_in_set2() {
-    let map = map_charsets(r"\a", "@");
+    let map = map_charsets(r"\a", "@").unwrap();
 
     assert_eq!(&"@", map.get("\u{07}").unwrap());
 }
@@ -131,7 +125,7 @@ fn should_map_escape_in_set2() {
 
 #[test]
 fn should_map_escapes_in_set2() {
-    let map = map_charsets("qwert", r"\n\t\v\b\r");
+    let map = map_charsets("qwert", r"\n\t\v\b\r").unwrap();
 
     assert_eq!(&"\n", map.get("q").unwrap());
     assert_eq!(&"\t", map.get("w").unwrap());
@@ -143,7 +137,7 @@ fn should_map_escapes_in_set2() {
 
 #[test]
 fn should_map_escapes_in_mixed_set2() {
-    let map = map_charsets("·^v_=-", r" \n\t+|/");
+    let map = map_charsets("·^v_=-", r" \n\t+|/").unwrap();
 
     assert_eq!(&" ", map.get("·").unwrap());
     assert_eq!(&"\n", map.get("^").unwrap());
@@ -152,3 +146,14 @@ fn should_map_escapes_in_mixed_set2() {
     assert_eq!(&"/", map.get("-").unwrap());
     assert_eq!(&"|", map.get("=").unwrap());
 }
+
+
+#[test]
+fn should_map_to_targets_of_several_graphemes() {
+    let map = map_targets("ЖЪa-c", &["Zh", "", "A", "B"]).unwrap();
+
+    assert_eq!("Zh", map["Ж"]);
+    assert_eq!("", map["Ъ"]);
+    assert_eq!("B", map["b"]);
+    assert_eq!("B", map["c"]);
+}
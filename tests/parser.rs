@@ -1,65 +1,65 @@
 use rstest::rstest;
 
-use tr::parser::parse;
+use tr::parser::{parse, parse_extended, parse_set, parse_set_extended};
 
 
 #[test]
 fn backslash_should_produce_backslash() {
-    assert_eq!(r"\", parse(r"\\"));
+    assert_eq!(r"\", parse(r"\\").unwrap());
 }
 
 
 #[test]
 fn backslash_8_should_produce_8() {
-    assert_eq!("8", parse(r"\8"));
+    assert_eq!("8", parse(r"\8").unwrap());
 }
 
 
 #[test]
 fn backslash_9_should_produce_9() {
-    assert_eq!("9", parse(r"\9"));
+    assert_eq!("9", parse(r"\9").unwrap());
 }
 
 
 #[test]
 fn bel_escape_should_produce_bel() {
-    assert_eq!("\u{07}", parse(r"\a"));
+    assert_eq!("\u{07}", parse(r"\a").unwrap());
 }
 
 
 #[test]
 fn backspace_escape_should_produce_backspace() {
-    assert_eq!("\u{08}", parse(r"\b"));
+    assert_eq!("\u{08}", parse(r"\b").unwrap());
 }
 
 
 #[test]
 fn formfeed_escape_should_produce_formfeed() {
-    assert_eq!("\u{0c}", parse(r"\f"));
+    assert_eq!("\u{0c}", parse(r"\f").unwrap());
 }
 
 
 #[test]
 fn newline_escape_should_produce_newline() {
-    assert_eq!("\n", parse(r"\n"));
+    assert_eq!("\n", parse(r"\n").unwrap());
 }
 
 
 #[test]
 fn cr_escape_should_produce_cr() {
-    assert_eq!("\r", parse(r"\r"));
+    assert_eq!("\r", parse(r"\r").unwrap());
 }
 
 
 #[test]
 fn tab_escape_should_produce_tab() {
-    assert_eq!("\t", parse(r"\t"));
+    assert_eq!("\t", parse(r"\t").unwrap());
 }
 
 
 #[test]
 fn vertical_tab_escape_should_produce_vertical_tab() {
-    assert_eq!("\u{0b}", parse(r"\v"));
+    assert_eq!("\u{0b}", parse(r"\v").unwrap());
 }
 
 
@@ -71,90 +71,90 @@ fn vertical_tab_escape_should_produce_vertical_tab() {
 fn character_range_should_produce_characters(case: (&str, &str)) {
     let (range, expected) = case;
 
-    assert_eq!(expected, parse(range));
+    assert_eq!(expected, parse(range).unwrap());
 }
 
 
 #[test]
 fn character_range_used_as_verbose_spelling_for_character() {
-    assert_eq!("5", parse("5-5"));
+    assert_eq!("5", parse("5-5").unwrap());
 }
 
 
 #[test]
 fn alnum_class_should_expand() {
     assert_eq!("0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz",
-               parse("[:alnum:]"));
+               parse("[:alnum:]").unwrap());
 }
 
 
 #[test]
 fn alpha_class_should_expand() {
     assert_eq!("ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz",
-               parse("[:alpha:]"));
+               parse("[:alpha:]").unwrap());
 }
 
 
 #[test]
 fn blank_class_should_expand() {
-    assert_eq!("\t ", parse("[:blank:]"));
+    assert_eq!("\t ", parse("[:blank:]").unwrap());
 }
 
 
 #[test]
 fn cntrl_class_should_expand() {
-    assert_eq!("\u{0}\u{1}\u{2}\u{3}\u{4}\u{5}\u{6}\u{7}\u{8}\t\n\u{b}\u{c}\r\u{e}\u{f}\u{10}\u{11}\u{12}\u{13}\u{14}\u{15}\u{16}\u{17}\u{18}\u{19}\u{1a}\u{1b}\u{1c}\u{1d}\u{1e}\u{1f}\u{7f}", parse("[:cntrl:]"));
+    assert_eq!("\u{0}\u{1}\u{2}\u{3}\u{4}\u{5}\u{6}\u{7}\u{8}\t\n\u{b}\u{c}\r\u{e}\u{f}\u{10}\u{11}\u{12}\u{13}\u{14}\u{15}\u{16}\u{17}\u{18}\u{19}\u{1a}\u{1b}\u{1c}\u{1d}\u{1e}\u{1f}\u{7f}", parse("[:cntrl:]").unwrap());
 }
 
 
 #[test]
 fn digit_class_should_expand() {
-    assert_eq!("0123456789", parse("[:digit:]"));
+    assert_eq!("0123456789", parse("[:digit:]").unwrap());
 }
 
 
 #[test]
 fn graph_class_should_expand() {
     assert_eq!("!\"#$%&\'()*+,-./0123456789:;<=>?@ABCDEFGHIJKLMNOPQRSTUVWXYZ[\\]^_`abcdefghijklmnopqrstuvwxyz{|}~",
-               parse("[:graph:]"));
+               parse("[:graph:]").unwrap());
 }
 
 
 #[test]
 fn lower_class_should_expand() {
-    assert_eq!("abcdefghijklmnopqrstuvwxyz", parse("[:lower:]"));
+    assert_eq!("abcdefghijklmnopqrstuvwxyz", parse("[:lower:]").unwrap());
 }
 
 
 #[test]
 fn print_class_should_expand() {
     assert_eq!(" !\"#$%&\'()*+,-./0123456789:;<=>?@ABCDEFGHIJKLMNOPQRSTUVWXYZ[\\]^_`abcdefghijklmnopqrstuvwxyz{|}~",
-               parse("[:print:]"));
+               parse("[:print:]").unwrap());
 }
 
 
 #[test]
 fn punct_class_should_expand() {
     // $ python3 -c 'for i in range(128): print(chr(i), end="")' | tr -cd '[:punct:]'
-    assert_eq!("!\"#$%&'()*+,-./:;<=>?@[\\]^_`{|}~", parse("[:punct:]"));
+    assert_eq!("!\"#$%&'()*+,-./:;<=>?@[\\]^_`{|}~", parse("[:punct:]").unwrap());
 }
 
 
 #[test]
 fn space_class_should_expand() {
-    assert_eq!("\t\n\u{b}\u{c}\r ", parse("[:space:]"));
+    assert_eq!("\t\n\u{b}\u{c}\r ", parse("[:space:]").unwrap());
 }
 
 
 #[test]
 fn upper_class_should_expand() {
-    assert_eq!("ABCDEFGHIJKLMNOPQRSTUVWXYZ", parse("[:upper:]"));
+    assert_eq!("ABCDEFGHIJKLMNOPQRSTUVWXYZ", parse("[:upper:]").unwrap());
 }
 
 
 #[test]
 fn xdigit_class_should_expand() {
-    assert_eq!("0123456789ABCDEFabcdef", parse("[:xdigit:]"));
+    assert_eq!("0123456789ABCDEFabcdef", parse("[:xdigit:]").unwrap());
 }
 
 
@@ -162,3 +162,104 @@ fn xdigit_class_should_expand() {
 //
 //      $ tr '_-=' '012'
 //      tr: range-endpoints of '_-=' are in reverse collating sequence order
+#[test]
+fn reversed_range_should_produce_error() {
+    assert_eq!(parse("ab_-=").unwrap_err(),
+               "range-endpoints of ‘_-=’ are in reverse collating sequence order\n  ab_-=\n    ^^^");
+    assert!(parse_set(r"\u{434}-\u{430}").is_err());
+}
+
+
+#[test]
+fn unicode_escape_range_should_produce_characters() {
+    assert_eq!("абвгд", parse(r"\u{430}-\u{434}").unwrap());
+}
+
+
+#[test]
+fn named_escape_should_produce_character() {
+    assert_eq!("a\u{a0}b", parse(r"a\N{NO-BREAK SPACE}b").unwrap());
+}
+
+
+#[test]
+fn unknown_character_name_should_produce_error() {
+    assert_eq!(parse(r"ab\N{NO SUCH CHARACTER}").unwrap_err(),
+               "invalid escape sequence ‘\\N{NO SUCH CHARACTER}’\n  \
+                ab\\N{NO SUCH CHARACTER}\n    ^^^^^^^^^^^^^^^^^^^^^");
+}
+
+
+#[test]
+fn property_class_should_expand_in_code_point_order() {
+    assert_eq!("0123456789", &parse(r"\p{Nd}").unwrap()[..10]);
+}
+
+
+#[test]
+fn emoji_set_should_match_whole_sequences() {
+    let set = parse_set("[:emoji:]").unwrap();
+
+    assert!(set.contains("👍🏽"));
+    assert!(set.contains("1\u{fe0f}\u{20e3}"));
+    assert!(set.contains("🇫🇷"));
+    assert!(!set.contains("1"));
+    assert!(!set.contains("#"));
+}
+
+
+#[test]
+fn emoji_class_should_not_expand_to_lone_components() {
+    let expanded = parse("[:emoji:]").unwrap();
+
+    assert!(expanded.contains('👍'));
+    // a regional indicator and a skin tone are only emoji in a sequence
+    assert!(!expanded.contains('\u{1f1eb}'));
+    assert!(!expanded.contains('\u{1f3fd}'));
+    assert!(parse_set("[:emoji:]").unwrap().contains("🇫🇷"));
+}
+
+
+#[rstest(
+    case => [("a-j--[aeiou]", "bcdfghj"), ("a-j&&[aeiou]", "aei"), ("[a-e]--[b-d]x", "aex"),
+             ("a-j--[aeiou]--[bc]", "dfghj"), ("[:digit:]--[[0-2]&&[1-9]]", "03456789")]
+)]
+fn extended_set_should_combine_operands(case: (&str, &str)) {
+    let (s, expected) = case;
+
+    assert_eq!(parse_extended(s).unwrap(), expected);
+}
+
+
+#[rstest(
+    case => [("--[a]", "missing operand before ‘--’\n  --[a]\n  ^^"),
+             ("a&&", "missing operand after ‘&&’\n  a&&\n   ^^"),
+             ("[a--]", "missing operand after ‘--’\n  [a--]\n    ^^"),
+             ("[abc", "missing ‘]’ in set\n  [abc\n  ^")]
+)]
+fn malformed_extended_set_should_produce_error(case: (&str, &str)) {
+    let (s, expected) = case;
+
+    assert_eq!(parse_extended(s).unwrap_err(), expected);
+}
+
+
+#[test]
+fn extended_set_difference_should_match_emoji_sequences() {
+    let set = parse_set_extended("[:emoji:]--[❤️]").unwrap();
+
+    assert!(set.contains("👍🏽"));
+    assert!(!set.contains("❤️"));
+}
+
+
+#[rstest(
+    case => [("a-z[:alfa:]", "invalid character class ‘[:alfa:]’\n  a-z[:alfa:]\n     ^^^^^^^^"),
+             ("é[:foo:]", "invalid character class ‘[:foo:]’\n  é[:foo:]\n   ^^^^^^^"),
+             ("[x*09]", "invalid repeat count ‘09’ in [c*n] construct\n  [x*09]\n  ^^^^^^")]
+)]
+fn set_error_should_point_at_offending_part(case: (&str, &str)) {
+    let (s, expected) = case;
+
+    assert_eq!(parse(s).unwrap_err(), expected);
+}
@@ -0,0 +1,63 @@
+use std::collections::HashSet;
+
+use bstr::ByteSlice;
+
+use tr::preset::{PRESETS, lookup};
+
+
+#[test]
+fn every_preset_should_be_found_by_name() {
+    for preset in PRESETS {
+        assert_eq!(preset.name, lookup(preset.name).unwrap().name);
+    }
+}
+
+
+#[test]
+fn unknown_preset_should_not_be_found() {
+    assert!(lookup("").is_none());
+    assert!(lookup("ASCII-FOLD").is_none());
+}
+
+
+#[test]
+fn preset_sources_should_not_repeat_graphemes() {
+    for preset in PRESETS {
+        let sources = preset.sources();
+        let graphemes = sources.as_bytes().graphemes().collect::<Vec<_>>();
+        let unique = graphemes.iter().collect::<HashSet<_>>();
+
+        assert_eq!(graphemes.len(), unique.len(), "{}", preset.name);
+    }
+}
+
+
+#[test]
+fn preset_set2_should_not_be_longer_than_set1() {
+    for preset in PRESETS {
+        let len1 = preset.set1.as_bytes().graphemes().count();
+        let len2 = preset.set2.as_bytes().graphemes().count();
+
+        assert!(len2 == len1 || len2 == 1, "{}", preset.name);
+    }
+}
+
+
+#[test]
+fn preset_pairs_should_translate_single_graphemes() {
+    for preset in PRESETS {
+        for (source, _) in preset.pairs {
+            assert_eq!(1, source.as_bytes().graphemes().count(), "{}", preset.name);
+        }
+    }
+}
+
+
+#[test]
+fn mapping_should_include_sets_and_pairs() {
+    let map = lookup("cyrillic-latin").unwrap().mapping();
+
+    assert_eq!("B", map["Б"]);
+    assert_eq!("Shch", map["Щ"]);
+    assert_eq!("", map["ь"]);
+}
@@ -0,0 +1,96 @@
+use std::io::Cursor;
+
+use tr::arg_parser::parse_args;
+use tr::command::process;
+use tr::stats::Stats;
+
+
+fn _stats(args: &[&str], stdin: &str) -> Stats {
+    let config = parse_args(args).unwrap();
+    let mut output = Vec::new();
+
+    process(&config, Cursor::new(stdin), &mut output).unwrap()
+}
+
+
+#[test]
+fn should_count_bytes_and_graphemes_read_and_written() {
+    let stats = _stats(&["tr", "--stats", "-d", "é"], "café!");
+
+    assert_eq!(stats.bytes_read, 6);
+    assert_eq!(stats.graphemes_read, 5);
+    assert_eq!(stats.bytes_written, 4);
+    assert_eq!(stats.graphemes_written, 4);
+    assert_eq!(stats.deleted, 1);
+}
+
+
+#[test]
+fn should_count_each_byte_once_in_bytes_mode() {
+    let config = parse_args(&["tr", "--stats", "--bytes", "-d", "\\351"]).unwrap();
+    let mut output = Vec::new();
+
+    let stats = process(&config, Cursor::new(&b"caf\xe9\xff!"[..]), &mut output).unwrap();
+
+    assert_eq!(output, b"caf\xff!");
+    assert_eq!(stats.bytes_read, 6);
+    assert_eq!(stats.bytes_written, 5);
+}
+
+
+#[test]
+fn should_count_only_graphemes_actually_translated() {
+    let stats = _stats(&["tr", "--stats", "ab", "aB"], "abcab");
+
+    assert_eq!(stats.translated, 2);
+}
+
+
+#[test]
+fn should_count_each_effect_of_each_stage() {
+    let stats = _stats(&["tr", "--stats", "-e", "A-Z", "a-z", "-e", "-d", "!", "-e", "-s", " "],
+                       "Hi  there!!");
+
+    assert_eq!(stats.translated, 1);
+    assert_eq!(stats.deleted, 2);
+    assert_eq!(stats.squeezed, 1);
+    assert_eq!(stats.graphemes_written, 8);
+}
+
+
+#[test]
+fn frequencies_should_count_set1_members_read() {
+    let stats = _stats(&["tr", "--stats=freq", "-d", "a-c"], "abracadabra");
+
+    assert_eq!(stats.frequencies["a"], 5);
+    assert_eq!(stats.frequencies["b"], 2);
+    assert_eq!(stats.frequencies.get("r"), None);
+}
+
+
+#[test]
+fn stats_should_not_be_counted_unless_requested() {
+    let stats = _stats(&["tr", "-d", "a"], "banana");
+
+    assert_eq!(stats.graphemes_read, 0);
+}
+
+
+#[test]
+fn frequencies_should_count_complement_members_with_c() {
+    let stats = _stats(&["tr", "--stats=freq", "-cd", "a-c"], "abracadabra");
+
+    assert_eq!(stats.frequencies["r"], 2);
+    assert_eq!(stats.frequencies["d"], 1);
+    assert_eq!(stats.frequencies.get("a"), None);
+    assert_eq!(stats.frequencies.get(""), None);
+}
+
+
+#[test]
+fn report_should_list_frequencies_most_frequent_first() {
+    let report = _stats(&["tr", "--stats=freq", "ab", "xy"], "abb").report();
+
+    assert!(report.starts_with("bytes read: 3\n"));
+    assert!(report.ends_with("SET1 frequencies:\n  ‘b’ U+0062: 2\n  ‘a’ U+0061: 1\n"));
+}